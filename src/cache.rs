@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    Contests,
+    Problems,
+    Results,
+    Details,
+}
+
+impl CacheKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheKind::Contests => "contests",
+            CacheKind::Problems => "problems",
+            CacheKind::Results => "results",
+            CacheKind::Details => "details",
+        }
+    }
+
+    /// How long an entry stays valid, `None` means forever
+    pub fn ttl(&self) -> Option<Duration> {
+        match self {
+            CacheKind::Contests => Some(Duration::from_secs(24 * 60 * 60)),
+            CacheKind::Problems => Some(Duration::from_secs(60 * 60)),
+            CacheKind::Results => Some(Duration::from_secs(60)),
+            CacheKind::Details => None,
+        }
+    }
+}
+
+pub trait Cache {
+    fn load(&self, kind: CacheKind, key: &str) -> Option<String>;
+    /// Like `load`, but expired entries are returned too
+    fn load_stale(&self, kind: CacheKind, key: &str) -> Option<String>;
+    fn store(&self, kind: CacheKind, key: &str, value: &str);
+    fn invalidate(&self, kind: CacheKind);
+    fn clear(&self);
+}
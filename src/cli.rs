@@ -1,10 +1,76 @@
+use crate::completion;
+
 use clap::{Arg, ArgAction, Command};
+use clap_complete::engine::ArgValueCandidates;
+use std::time::Duration;
 
 pub fn build_cli() -> Command {
     Command::new("satori")
         .about("Satori CLI")
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .action(ArgAction::Set)
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .global(true)
+                .help("Output format"),
+        )
+        .arg(
+            Arg::new("url")
+                .long("url")
+                .action(ArgAction::Set)
+                .global(true)
+                .help("Base URL of Satori, overrides SATORI_URL"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .action(ArgAction::Set)
+                .global(true)
+                .help("Account profile to use"),
+        )
+        .arg(
+            Arg::new("non-interactive")
+                .long("non-interactive")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Never prompt, fail if not logged in"),
+        )
+        .arg(
+            Arg::new("password-stdin")
+                .long("password-stdin")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Read password from stdin, login from SATORI_LOGIN"),
+        )
+        .arg(
+            Arg::new("connect-timeout")
+                .long("connect-timeout")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64))
+                .global(true)
+                .help("Connection timeout in seconds"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64))
+                .global(true)
+                .help("Request timeout in seconds"),
+        )
+        .arg(
+            Arg::new("retries")
+                .long("retries")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u32))
+                .global(true)
+                .help("Number of retries of failed requests"),
+        )
+        .subcommand(login_command())
         .subcommand(username_command())
         .subcommand(contests_command())
         .subcommand(details_command())
@@ -14,6 +80,41 @@ pub fn build_cli() -> Command {
         .subcommand(results_command())
         .subcommand(status_command())
         .subcommand(submit_command())
+        .subcommand(watch_command())
+        .subcommand(test_command())
+        .subcommand(profile_command())
+        .subcommand(debug_command())
+        .subcommand(completions_command())
+}
+
+fn contest_arg() -> Arg {
+    Arg::new("contest")
+        .short('c')
+        .long("contest")
+        .action(ArgAction::Set)
+        .add(ArgValueCandidates::new(completion::contest_candidates))
+        .help("Prefix of contest name")
+}
+
+fn problem_arg() -> Arg {
+    Arg::new("problem")
+        .short('p')
+        .long("problem")
+        .action(ArgAction::Set)
+        .add(ArgValueCandidates::new(completion::problem_candidates))
+        .help("Problem code")
+}
+
+fn login_command() -> Command {
+    Command::new("login")
+        .about("Log in and store the token")
+        .arg(
+            Arg::new("user")
+                .short('u')
+                .long("user")
+                .action(ArgAction::Set)
+                .help("Login, asked for if not given"),
+        )
 }
 
 fn username_command() -> Command {
@@ -42,14 +143,7 @@ fn contests_command() -> Command {
 fn details_command() -> Command {
     Command::new("details")
         .about("Show details of submission")
-        .arg(
-            Arg::new("contest")
-                .short('c')
-                .long("contest")
-                .action(ArgAction::Set)
-                .required(true)
-                .help("Prefix of contest name"),
-        )
+        .arg(contest_arg())
         .arg(
             Arg::new("submission")
                 .short('s')
@@ -70,14 +164,7 @@ fn details_command() -> Command {
 fn problems_command() -> Command {
     Command::new("problems")
         .about("List problems")
-        .arg(
-            Arg::new("contest")
-                .short('c')
-                .long("contest")
-                .action(ArgAction::Set)
-                .required(true)
-                .help("Prefix of contest name"),
-        )
+        .arg(contest_arg())
         .arg(
             Arg::new("force")
                 .short('f')
@@ -90,79 +177,123 @@ fn problems_command() -> Command {
 fn pdf_command() -> Command {
     Command::new("pdf")
         .about("Download pdf")
+        .arg(contest_arg())
+        .arg(problem_arg())
         .arg(
-            Arg::new("contest")
-                .short('c')
-                .long("contest")
+            Arg::new("output")
+                .short('o')
+                .long("output")
                 .action(ArgAction::Set)
-                .required(true)
-                .help("Prefix of contest name"),
+                .default_value(".")
+                .help("Directory to save the pdf in"),
         )
         .arg(
-            Arg::new("problem")
-                .short('p')
-                .long("problem")
-                .action(ArgAction::Set)
-                .required(true)
-                .help("Problem code"),
+            Arg::new("open")
+                .long("open")
+                .action(ArgAction::SetTrue)
+                .help("Open the pdf after downloading"),
         )
         .arg(
             Arg::new("force")
                 .short('f')
                 .long("force")
                 .action(ArgAction::SetTrue)
-                .help("Force refresh"),
+                .help("Download even if the pdf already exists"),
         )
 }
 
 fn submit_command() -> Command {
     Command::new("submit")
         .about("Submit solution")
+        .arg(contest_arg())
+        .arg(problem_arg())
         .arg(
-            Arg::new("contest")
-                .short('c')
-                .long("contest")
+            Arg::new("file")
+                .short('f')
+                .long("file")
                 .action(ArgAction::Set)
-                .required(true)
-                .help("Prefix of contest name"),
+                .help("Path to solution file"),
         )
         .arg(
-            Arg::new("problem")
-                .short('p')
-                .long("problem")
+            Arg::new("lang")
+                .short('l')
+                .long("lang")
+                .action(ArgAction::Set)
+                .help("Language, detected from the file extension by default"),
+        )
+        .arg(
+            Arg::new("wait")
+                .short('w')
+                .long("wait")
+                .action(ArgAction::SetTrue)
+                .help("Wait until the submission is judged"),
+        )
+        .arg(wait_timeout_arg())
+}
+
+fn watch_command() -> Command {
+    Command::new("watch")
+        .about("Wait until submission is judged and show its details")
+        .arg(contest_arg())
+        .arg(
+            Arg::new("submission")
+                .short('s')
+                .long("submission")
                 .action(ArgAction::Set)
                 .required(true)
-                .help("Problem code"),
+                .help("Submission ID"),
         )
+        .arg(wait_timeout_arg())
+}
+
+fn wait_timeout_arg() -> Arg {
+    Arg::new("wait-timeout")
+        .long("wait-timeout")
+        .action(ArgAction::Set)
+        .value_parser(clap::value_parser!(u64))
+        .help("Seconds to wait for the verdict, 600 by default")
+}
+
+fn test_command() -> Command {
+    Command::new("test")
+        .about("Run solution against local sample tests")
+        .arg(problem_arg())
         .arg(
             Arg::new("file")
                 .short('f')
                 .long("file")
                 .action(ArgAction::Set)
-                .required(true)
                 .help("Path to solution file"),
         )
-}
-
-fn status_command() -> Command {
-    Command::new("status")
-        .about("Show status of the problem")
         .arg(
-            Arg::new("contest")
-                .short('c')
-                .long("contest")
-                .action(ArgAction::Set)
-                .required(true)
-                .help("Prefix of contest name"),
+            Arg::new("tests").long("tests").action(ArgAction::Set).help(
+                "Directory with tests, tests/<problem> or tests next to the solution by default",
+            ),
         )
         .arg(
-            Arg::new("problem")
-                .short('p')
-                .long("problem")
+            Arg::new("time-limit")
+                .short('t')
+                .long("time-limit")
                 .action(ArgAction::Set)
-                .required(true)
-                .help("Problem code"),
+                .value_parser(parse_time_limit)
+                .default_value("2")
+                .help("Time limit of a single test in seconds"),
         )
+}
+
+fn parse_time_limit(value: &str) -> Result<Duration, String> {
+    let seconds = value.parse::<f64>().map_err(|error| error.to_string())?;
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(limit) if !limit.is_zero() => Ok(limit),
+        _ => Err("expected a positive number of seconds".to_string()),
+    }
+}
+
+fn status_command() -> Command {
+    Command::new("status")
+        .about("Show status of the problem")
+        .arg(contest_arg())
+        .arg(problem_arg())
         .arg(
             Arg::new("force")
                 .short('f')
@@ -191,21 +322,8 @@ fn status_command() -> Command {
 fn results_command() -> Command {
     Command::new("results")
         .about("Show results of submitted solutions")
-        .arg(
-            Arg::new("contest")
-                .short('c')
-                .long("contest")
-                .action(ArgAction::Set)
-                .required(true)
-                .help("Prefix of contest name"),
-        )
-        .arg(
-            Arg::new("problem")
-                .short('p')
-                .long("problem")
-                .action(ArgAction::Set)
-                .help("Problem code"),
-        )
+        .arg(contest_arg())
+        .arg(problem_arg())
         .arg(
             Arg::new("limit")
                 .short('l')
@@ -223,6 +341,77 @@ fn results_command() -> Command {
         )
 }
 
+fn profile_command() -> Command {
+    let name = Arg::new("name")
+        .action(ArgAction::Set)
+        .required(true)
+        .help("Profile name");
+
+    Command::new("profile")
+        .about("Manage account profiles")
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("List profiles"))
+        .subcommand(
+            Command::new("add")
+                .about("Add profile or update its settings")
+                .arg(name.clone())
+                .arg(
+                    Arg::new("contest")
+                        .short('c')
+                        .long("contest")
+                        .action(ArgAction::Set)
+                        .add(ArgValueCandidates::new(completion::contest_candidates))
+                        .help("Default contest of the profile"),
+                ),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Remove profile together with its token")
+                .arg(name.clone()),
+        )
+        .subcommand(
+            Command::new("use")
+                .about("Use profile by default")
+                .arg(name),
+        )
+}
+
 fn logout_command() -> Command {
     Command::new("logout").about("Logout from Satori")
 }
+
+fn debug_command() -> Command {
+    Command::new("debug")
+        .about("Tools for developing satori-cli")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("dump-page")
+                .about("Save a page as a parser test fixture, with the token redacted")
+                .arg(
+                    Arg::new("path")
+                        .action(ArgAction::Set)
+                        .required(true)
+                        .help("Path of the page, e.g. /contest/select"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .action(ArgAction::Set)
+                        .help("File to save the page to, stdout by default"),
+                ),
+        )
+}
+
+fn completions_command() -> Command {
+    Command::new("completions")
+        .about("Print shell completion script")
+        .after_help("Add `source <(satori-cli completions bash)` to ~/.bashrc, or the equivalent for your shell")
+        .arg(
+            Arg::new("shell")
+                .action(ArgAction::Set)
+                .required(true)
+                .value_parser(["bash", "zsh", "fish"])
+                .help("Shell to complete in"),
+        )
+}
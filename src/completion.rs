@@ -0,0 +1,130 @@
+use crate::cache::{Cache, CacheKind};
+use crate::config::Config;
+use crate::file_cache::FileCache;
+use crate::profile::Profiles;
+use crate::satori::{Contest, ContestStatus, Problem};
+
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+
+/// Environment variable that switches the binary into completion mode
+pub const COMPLETE_VARIABLE: &str = "COMPLETE";
+pub const BIN_NAME: &str = "satori-cli";
+
+/// Prints the script that registers completion in `shell`
+pub fn write_registration(shell: &str, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells.completer(shell).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unsupported shell {}", shell),
+        )
+    })?;
+    completer.write_registration(COMPLETE_VARIABLE, BIN_NAME, BIN_NAME, BIN_NAME, out)
+}
+
+/// Names of cached contests, completion never hits the network
+pub fn contest_candidates() -> Vec<CompletionCandidate> {
+    let cache = profile_cache();
+    cached_contests(&cache)
+        .into_iter()
+        .map(|contest| CompletionCandidate::new(contest.name).help(Some(contest.id.into())))
+        .collect()
+}
+
+/// Codes of cached problems of the contest given on the command line or in configuration,
+/// or of all cached contests if it is unknown
+pub fn problem_candidates() -> Vec<CompletionCandidate> {
+    let cache = profile_cache();
+    let contests = cached_contests(&cache);
+    let matching = match contest_prefix() {
+        Some(prefix) => contests
+            .iter()
+            .filter(|contest| contest.id.starts_with(&prefix) || contest.name.starts_with(&prefix))
+            .collect::<Vec<&Contest>>(),
+        None => vec![],
+    };
+    let contests = match matching.is_empty() {
+        true => contests.iter().collect(),
+        false => matching,
+    };
+
+    let mut candidates: Vec<CompletionCandidate> = vec![];
+    for contest in contests {
+        for problem in load::<Vec<Problem>>(&cache, CacheKind::Problems, &contest.id)
+            .into_iter()
+            .flatten()
+        {
+            if candidates
+                .iter()
+                .all(|candidate| candidate.get_value() != problem.code.as_str())
+            {
+                candidates
+                    .push(CompletionCandidate::new(problem.code).help(Some(problem.name.into())));
+            }
+        }
+    }
+    candidates
+}
+
+fn cached_contests(cache: &FileCache) -> Vec<Contest> {
+    ["joined", "archived"]
+        .iter()
+        .filter_map(|key| load::<Vec<Contest>>(cache, CacheKind::Contests, key))
+        .flatten()
+        .filter(|contest| contest.status != ContestStatus::Pending)
+        .collect()
+}
+
+fn load<V: serde::de::DeserializeOwned>(
+    cache: &FileCache,
+    kind: CacheKind,
+    key: &str,
+) -> Option<V> {
+    serde_json::from_str(&cache.load_stale(kind, key)?).ok()
+}
+
+fn profile_cache() -> FileCache {
+    let name = command_line_value("--profile", None)
+        .or_else(|| config().and_then(|config| config.profile))
+        .or_else(|| {
+            Profiles::load()
+                .ok()
+                .map(|profiles| profiles.current().to_string())
+        })
+        .filter(|name| Profiles::validate_name(name).is_ok())
+        .unwrap_or(Profiles::DEFAULT_PROFILE.to_string());
+    let url = config()
+        .unwrap_or_default()
+        .url(command_line_value("--url", None).as_deref());
+    FileCache::new(&Profiles::cache_path(
+        &name,
+        Profiles::instance(&url).as_deref(),
+    ))
+}
+
+fn contest_prefix() -> Option<String> {
+    command_line_value("--contest", Some("-c"))
+        .or_else(|| config().and_then(|config| config.contest))
+}
+
+fn config() -> Option<Config> {
+    Config::discover(&std::env::current_dir().ok()?).ok()
+}
+
+/// Value of an option in the words being completed, which are passed after `--`
+fn command_line_value(long: &str, short: Option<&str>) -> Option<String> {
+    let words = std::env::args()
+        .skip_while(|word| word != "--")
+        .collect::<Vec<String>>();
+    let prefix = format!("{}=", long);
+    words.iter().enumerate().find_map(|(i, word)| {
+        if let Some(value) = word.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+        if word == long || Some(word.as_str()) == short {
+            return words.get(i + 1).filter(|value| !value.is_empty()).cloned();
+        }
+        None
+    })
+}
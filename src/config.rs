@@ -0,0 +1,143 @@
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+
+/// Default values for command arguments and global options
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub contest: Option<String>,
+    pub problem: Option<String>,
+    pub file: Option<String>,
+    pub profile: Option<String>,
+    pub credential_helper: Option<String>,
+    pub login_attempts: Option<u32>,
+    pub wait_timeout: Option<u64>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub token: TokenConfig,
+}
+
+/// `[network]` section, timeouts are in seconds
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub connect_timeout: Option<u64>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStorageKind {
+    Plain,
+    Encrypted,
+}
+
+/// `[token]` section, `key_file` is used instead of a passphrase by encrypted storage
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    pub storage: Option<TokenStorageKind>,
+    pub key_file: Option<String>,
+    pub cookie_name: Option<String>,
+}
+
+impl TokenConfig {
+    fn merge(self, other: TokenConfig) -> TokenConfig {
+        TokenConfig {
+            storage: other.storage.or(self.storage),
+            key_file: other.key_file.or(self.key_file),
+            cookie_name: other.cookie_name.or(self.cookie_name),
+        }
+    }
+}
+
+impl NetworkConfig {
+    fn merge(self, other: NetworkConfig) -> NetworkConfig {
+        NetworkConfig {
+            connect_timeout: other.connect_timeout.or(self.connect_timeout),
+            timeout: other.timeout.or(self.timeout),
+            retries: other.retries.or(self.retries),
+        }
+    }
+}
+
+impl Config {
+    pub const DEFAULT_URL: &str = "https://satori.tcs.uj.edu.pl";
+    pub const DEFAULT_TOKEN_NAME: &str = "satori_token";
+    const PROJECT_CONFIG_NAME: &str = "satori-cli.toml";
+    const GLOBAL_CONFIG_PATH: &str = "~/.config/satori-cli/config.toml";
+    const URL_VARIABLE: &str = "SATORI_URL";
+    const TOKEN_NAME_VARIABLE: &str = "SATORI_TOKEN_NAME";
+
+    /// Base URL of Satori, `--url` overrides `SATORI_URL` which overrides the config
+    pub fn url(&self, flag: Option<&str>) -> String {
+        flag.map(str::to_string)
+            .or_else(|| std::env::var(Config::URL_VARIABLE).ok())
+            .or_else(|| self.url.clone())
+            .unwrap_or(Config::DEFAULT_URL.to_string())
+    }
+
+    /// Name of the session cookie, `SATORI_TOKEN_NAME` overrides the config
+    pub fn token_name(&self) -> String {
+        std::env::var(Config::TOKEN_NAME_VARIABLE)
+            .ok()
+            .or_else(|| self.token.cookie_name.clone())
+            .unwrap_or(Config::DEFAULT_TOKEN_NAME.to_string())
+    }
+
+    /// Loads global config and every `satori-cli.toml` from the filesystem root down to `directory`,
+    /// values from files closer to `directory` take precedence.
+    pub fn discover(directory: &Path) -> Result<Config, String> {
+        let global_path = PathBuf::from(shellexpand::tilde(Config::GLOBAL_CONFIG_PATH).to_string());
+        let mut config = Config::load_if_exists(&global_path)?.unwrap_or_default();
+
+        let mut project_paths = directory
+            .ancestors()
+            .map(|ancestor| ancestor.join(Config::PROJECT_CONFIG_NAME))
+            .collect::<Vec<PathBuf>>();
+        project_paths.reverse();
+
+        for path in project_paths {
+            if let Some(project_config) = Config::load_if_exists(&path)? {
+                config = config.merge(project_config);
+            }
+        }
+        Ok(config)
+    }
+
+    fn load_if_exists(path: &Path) -> Result<Option<Config>, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+        let mut config: Config =
+            toml::from_str(&content).map_err(|error| format!("{}: {}", path.display(), error))?;
+
+        // Solution file is relative to the config that names it, not to the working directory
+        if let (Some(file), Some(directory)) = (&config.file, path.parent()) {
+            config.file = Some(directory.join(file).display().to_string());
+        }
+        Ok(Some(config))
+    }
+
+    /// Values set in `other` override values in `self`
+    fn merge(self, other: Config) -> Config {
+        Config {
+            contest: other.contest.or(self.contest),
+            problem: other.problem.or(self.problem),
+            file: other.file.or(self.file),
+            profile: other.profile.or(self.profile),
+            credential_helper: other.credential_helper.or(self.credential_helper),
+            login_attempts: other.login_attempts.or(self.login_attempts),
+            wait_timeout: other.wait_timeout.or(self.wait_timeout),
+            url: other.url.or(self.url),
+            network: self.network.merge(other.network),
+            token: self.token.merge(other.token),
+        }
+    }
+}
@@ -1,15 +1,20 @@
 use crate::satori::*;
+use crate::test_runner::LocalTestReport;
 
 pub trait SatoriDisplay {
     fn display_username(&self, username: &SatoriResult<String>);
     fn display_contests(&self, contests: &SatoriResult<Vec<Contest>>);
     fn display_details(&self, details: &SatoriResult<ResultDetails>);
-    fn display_login(&self, login: &SatoriResult<String>);
+    fn display_login(&self, login: &SatoriResult<Session>);
     fn display_logout(&self, logout: &SatoriResult<()>);
     fn display_problems(&self, problems: &SatoriResult<Vec<Problem>>);
-    fn display_pdf(&self, pdf: &SatoriResult<()>);
+    fn display_pdf(&self, pdf: &SatoriResult<String>);
     fn display_results(&self, results: &SatoriResult<Vec<ShortResult>>);
-    fn display_status(&self, status: &SatoriResult<String>);
-    fn display_submit(&self, submit: &SatoriResult<()>);
+    fn display_status(&self, status: &SatoriResult<ProblemStatus>);
+    fn display_submit(&self, submit: &SatoriResult<Submission>);
+    fn display_test_results(&self, report: &SatoriResult<LocalTestReport>);
+    fn display_waiting(&self, submission: &str);
+    fn display_waiting_done(&self);
+    fn display_watch(&self, details: &SatoriResult<ResultDetails>);
     fn display_error(&self, error: &SatoriError);
 }
@@ -0,0 +1,181 @@
+use crate::token_storage::TokenStorage;
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub enum KeySource {
+    /// Read from `SATORI_TOKEN_PASSPHRASE`, or asked for when needed if `prompt` is set
+    Passphrase {
+        prompt: bool,
+    },
+    KeyFile(PathBuf),
+}
+
+/// Encrypts the token with a key derived from a passphrase or a key file
+/// and keeps the result in another storage.
+///
+/// Stored value is `base64(salt || nonce || ciphertext)`.
+pub struct EncryptedTokenStorage<T: TokenStorage> {
+    inner: T,
+    key_source: KeySource,
+    secret: OnceCell<Vec<u8>>,
+    /// Derived keys by salt, Argon2 is too slow to run on every request
+    keys: RefCell<HashMap<Vec<u8>, [u8; 32]>>,
+}
+
+impl<T: TokenStorage> EncryptedTokenStorage<T> {
+    const PASSPHRASE_VARIABLE: &str = "SATORI_TOKEN_PASSPHRASE";
+
+    pub fn new(inner: T, key_source: KeySource) -> Self {
+        Self {
+            inner,
+            key_source,
+            secret: OnceCell::new(),
+            keys: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn secret(&self) -> std::io::Result<&[u8]> {
+        if let Some(secret) = self.secret.get() {
+            return Ok(secret);
+        }
+
+        let secret = match &self.key_source {
+            KeySource::KeyFile(path) => std::fs::read(path)?,
+            KeySource::Passphrase { prompt } => match std::env::var(Self::PASSPHRASE_VARIABLE) {
+                Ok(passphrase) => passphrase.into_bytes(),
+                Err(_) if *prompt => rpassword::prompt_password("Token passphrase: ")?.into_bytes(),
+                Err(_) => {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("token passphrase not set in {}", Self::PASSPHRASE_VARIABLE),
+                    ))
+                }
+            },
+        };
+        if secret.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "empty token key"));
+        }
+        Ok(self.secret.get_or_init(|| secret))
+    }
+
+    fn cipher(&self, salt: &[u8]) -> std::io::Result<ChaCha20Poly1305> {
+        if let Some(key) = self.keys.borrow().get(salt) {
+            return Ok(ChaCha20Poly1305::new(Key::from_slice(key)));
+        }
+
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(self.secret()?, salt, &mut key)
+            .map_err(|error| Error::other(error.to_string()))?;
+        self.keys.borrow_mut().insert(salt.to_vec(), key);
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn encrypt(&self, token: &str) -> std::io::Result<String> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher(&salt)?
+            .encrypt(Nonce::from_slice(&nonce), token.as_bytes())
+            .map_err(|_| Error::other("token encryption failed"))?;
+        Ok(BASE64.encode([&salt[..], &nonce[..], &ciphertext[..]].concat()))
+    }
+
+    fn decrypt(&self, stored: &str) -> std::io::Result<String> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "cannot decrypt token");
+        let data = BASE64.decode(stored.trim()).map_err(|_| invalid())?;
+        if data.len() < SALT_LEN + NONCE_LEN {
+            return Err(invalid());
+        }
+
+        let (salt, rest) = data.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let token = self
+            .cipher(salt)?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        String::from_utf8(token).map_err(|_| invalid())
+    }
+}
+
+impl<T: TokenStorage> TokenStorage for EncryptedTokenStorage<T> {
+    fn load_token(&self) -> Option<String> {
+        let stored = self.inner.load_token()?;
+        match self.decrypt(&stored) {
+            Ok(token) => Some(token),
+            Err(error) => {
+                // Treated as no token at all, logging in again overwrites it
+                eprintln!("Warning: {}", error);
+                None
+            }
+        }
+    }
+
+    fn save_token(&self, token: &str) -> std::io::Result<()> {
+        let encrypted = self.encrypt(token)?;
+        self.inner.save_token(&encrypted)
+    }
+
+    fn clear_token(&self) -> std::io::Result<()> {
+        self.inner.clear_token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes::MemoryTokenStorage;
+
+    fn storage(name: &str, key: &str) -> EncryptedTokenStorage<MemoryTokenStorage> {
+        let path =
+            std::env::temp_dir().join(format!("satori-cli-key-{}-{}", std::process::id(), name));
+        std::fs::write(&path, key).unwrap();
+        let storage = EncryptedTokenStorage::new(
+            MemoryTokenStorage::default(),
+            KeySource::KeyFile(path.clone()),
+        );
+        // Key is read once, so the file is not needed afterwards
+        storage.secret().unwrap();
+        let _ = std::fs::remove_file(&path);
+        storage
+    }
+
+    #[test]
+    fn token_survives_round_trip() {
+        let storage = storage("round-trip", "secret key");
+        storage.save_token("token").unwrap();
+        assert_ne!(storage.inner.load_token().unwrap(), "token");
+        assert_eq!(storage.load_token(), Some("token".to_string()));
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let encrypted = storage("wrong-key", "secret key").encrypt("token").unwrap();
+        let other = storage("other-key", "another key");
+        assert!(other.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn tampered_token_is_rejected() {
+        let storage = storage("tampered", "secret key");
+        let mut data = BASE64.decode(storage.encrypt("token").unwrap()).unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        assert!(storage.decrypt(&BASE64.encode(data)).is_err());
+    }
+}
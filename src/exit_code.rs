@@ -0,0 +1,71 @@
+//! Exit codes of the process, so scripts can branch on the outcome of a command.
+//!
+//! Their meaning is documented in the "exit codes" section of `README.md`.
+
+use crate::satori::*;
+
+pub const SUCCESS: i32 = 0;
+pub const VERDICT_NOT_OK: i32 = 1;
+pub const USAGE: i32 = 2;
+pub const CONFIG: i32 = 3;
+pub const NOT_LOGGED_IN: i32 = 10;
+pub const LOGIN_FAILED: i32 = 11;
+pub const SESSION_REJECTED: i32 = 12;
+pub const CONNECTION_FAILED: i32 = 20;
+pub const PARSING_FAILED: i32 = 21;
+pub const WAIT_TIMED_OUT: i32 = 22;
+pub const CONTEST_NOT_FOUND: i32 = 30;
+pub const PROBLEM_NOT_FOUND: i32 = 31;
+pub const SUBMISSION_NOT_FOUND: i32 = 32;
+pub const SUBMISSION_CLOSED: i32 = 33;
+pub const AMBIGUOUS_NAME: i32 = 40;
+pub const INVALID_CHOICE: i32 = 41;
+pub const FILE_ERROR: i32 = 50;
+pub const UNSUPPORTED_LANGUAGE: i32 = 51;
+
+pub fn from_error(error: &SatoriError) -> i32 {
+    match error {
+        SatoriError::NotLoggedIn => NOT_LOGGED_IN,
+        SatoriError::LoginFailed => LOGIN_FAILED,
+        SatoriError::LoginAttemptsExceeded(_) => LOGIN_FAILED,
+        SatoriError::SessionRejected => SESSION_REJECTED,
+        SatoriError::ParsingFailed => PARSING_FAILED,
+        SatoriError::ConnectionFailed(_) => CONNECTION_FAILED,
+        SatoriError::InvalidChoice => INVALID_CHOICE,
+        SatoriError::AmbiguousContest(_) => AMBIGUOUS_NAME,
+        SatoriError::AmbiguousProblem(_) => AMBIGUOUS_NAME,
+        SatoriError::ContestNotFound => CONTEST_NOT_FOUND,
+        SatoriError::ProblemNotFound => PROBLEM_NOT_FOUND,
+        SatoriError::SubmissionNotFound => SUBMISSION_NOT_FOUND,
+        SatoriError::SubmissionClosed => SUBMISSION_CLOSED,
+        SatoriError::FileError(_) => FILE_ERROR,
+        SatoriError::UnsupportedLanguage(_) => UNSUPPORTED_LANGUAGE,
+        SatoriError::WatchTimedOut(_) => WAIT_TIMED_OUT,
+    }
+}
+
+/// `None` is a problem without any submission
+pub fn from_verdict(verdict: Option<&Verdict>) -> i32 {
+    match verdict {
+        Some(verdict) if verdict.is_accepted() => SUCCESS,
+        _ => VERDICT_NOT_OK,
+    }
+}
+
+pub fn from_result<T>(result: &SatoriResult<T>) -> i32 {
+    match result {
+        Ok(_) => SUCCESS,
+        Err(error) => from_error(error),
+    }
+}
+
+/// Like `from_result`, but a successful result is judged by its verdict
+pub fn from_verdict_result<T>(
+    result: &SatoriResult<T>,
+    verdict: impl Fn(&T) -> Option<&Verdict>,
+) -> i32 {
+    match result {
+        Ok(value) => from_verdict(verdict(value)),
+        Err(error) => from_error(error),
+    }
+}
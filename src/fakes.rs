@@ -0,0 +1,306 @@
+//! In-memory implementations of the traits, so tests run without network, terminal or files.
+
+use crate::cache::{Cache, CacheKind};
+use crate::display::SatoriDisplay;
+use crate::prompt::Prompt;
+use crate::satori::*;
+use crate::satori_client::{ClientError, ClientResult, SatoriClient};
+use crate::test_runner::LocalTestReport;
+use crate::token_storage::TokenStorage;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+macro_rules! fixture {
+    ($name:literal) => {
+        include_str!(concat!("../tests/fixtures/", $name, ".html"))
+    };
+}
+pub(crate) use fixture;
+
+pub const LOGIN: &str = "jsmith";
+pub const PASSWORD: &str = "secret";
+pub const TOKEN: &str = "fresh-token";
+
+/// Results fixture without the newest submission, which appears only after submitting
+fn results_before_submit() -> String {
+    let page = fixture!("results");
+    let start = page[..page.find("8812345").unwrap()].rfind("<tr>").unwrap();
+    let end = start + page[start..].find("</tr>").unwrap() + "</tr>".len();
+    format!("{}{}", &page[..start], &page[end..])
+}
+
+/// Serves fixture pages by path, or the logged-out page unless the token is `TOKEN`
+pub struct FakeClient {
+    routes: HashMap<String, String>,
+    token: RefCell<Option<String>>,
+    pub requests: Rc<RefCell<Vec<String>>>,
+}
+
+impl FakeClient {
+    pub fn new() -> FakeClient {
+        FakeClient {
+            routes: HashMap::new(),
+            token: RefCell::new(None),
+            requests: Rc::new(RefCell::new(vec![])),
+        }
+        .route("", fixture!("contest_select"))
+        .route("/login", fixture!("contest_select"))
+        .route("/contest/select", fixture!("contest_select"))
+        .route(
+            "/contest/select?archived=1",
+            fixture!("contest_select_archived"),
+        )
+        .route("/contest/5241304/problems", fixture!("problems"))
+        .route("/contest/5241304/submit?select=5241320", fixture!("submit"))
+        .route("/contest/5241304/submit", fixture!("results"))
+        .route(
+            "/contest/5241304/results?results_filter_problem=5241320",
+            &results_before_submit(),
+        )
+        .route("/contest/5241304/results/8812001", fixture!("details"))
+    }
+
+    pub fn route(mut self, path: &str, page: &str) -> FakeClient {
+        self.routes.insert(path.to_string(), page.to_string());
+        self
+    }
+
+    fn serve(&self, path: &str) -> ClientResult<String> {
+        self.requests.borrow_mut().push(path.to_string());
+        if self.token.borrow().as_deref() != Some(TOKEN) {
+            return Ok(fixture!("logged_out").to_string());
+        }
+        self.routes
+            .get(path)
+            .cloned()
+            .ok_or(ClientError::HttpStatus(404))
+    }
+}
+
+impl SatoriClient for FakeClient {
+    fn get_token(&self) -> Option<String> {
+        self.token.borrow().clone()
+    }
+
+    fn set_token(&self, token: &str) {
+        *self.token.borrow_mut() = Some(token.to_string());
+    }
+
+    fn get_token_expiry(&self) -> Option<SystemTime> {
+        None
+    }
+
+    fn get(&self, path: &str) -> ClientResult<String> {
+        self.serve(path)
+    }
+
+    fn get_bytes(&self, path: &str) -> ClientResult<Vec<u8>> {
+        self.serve(path).map(String::into_bytes)
+    }
+
+    /// Like Satori, a failed login leaves the old token in place
+    fn post(&self, path: &str, data: &[(&str, &str)]) -> ClientResult<String> {
+        if path == "/login"
+            && data.contains(&("login", LOGIN))
+            && data.contains(&("password", PASSWORD))
+        {
+            self.set_token(TOKEN);
+        }
+        self.serve(path)
+    }
+
+    fn submit_file(
+        &self,
+        path: &str,
+        _data: &[(&str, &str)],
+        _file_name: &str,
+        _file_path: &str,
+    ) -> ClientResult<String> {
+        self.serve(path)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryTokenStorage {
+    token: RefCell<Option<String>>,
+}
+
+impl MemoryTokenStorage {
+    pub fn with_token(token: &str) -> MemoryTokenStorage {
+        MemoryTokenStorage {
+            token: RefCell::new(Some(token.to_string())),
+        }
+    }
+}
+
+impl TokenStorage for MemoryTokenStorage {
+    fn load_token(&self) -> Option<String> {
+        self.token.borrow().clone()
+    }
+
+    fn save_token(&self, token: &str) -> std::io::Result<()> {
+        *self.token.borrow_mut() = Some(token.to_string());
+        Ok(())
+    }
+
+    fn clear_token(&self) -> std::io::Result<()> {
+        *self.token.borrow_mut() = None;
+        Ok(())
+    }
+}
+
+/// Entries never expire
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: RefCell<HashMap<(&'static str, String), String>>,
+}
+
+impl Cache for MemoryCache {
+    fn load(&self, kind: CacheKind, key: &str) -> Option<String> {
+        self.load_stale(kind, key)
+    }
+
+    fn load_stale(&self, kind: CacheKind, key: &str) -> Option<String> {
+        self.entries
+            .borrow()
+            .get(&(kind.name(), key.to_string()))
+            .cloned()
+    }
+
+    fn store(&self, kind: CacheKind, key: &str, value: &str) {
+        self.entries
+            .borrow_mut()
+            .insert((kind.name(), key.to_string()), value.to_string());
+    }
+
+    fn invalidate(&self, kind: CacheKind) {
+        self.entries
+            .borrow_mut()
+            .retain(|(name, _), _| *name != kind.name());
+    }
+
+    fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
+/// Answers with queued credentials and choices, then gives up
+#[derive(Default)]
+pub struct ScriptedPrompt {
+    credentials: RefCell<VecDeque<(String, String)>>,
+    choices: RefCell<VecDeque<usize>>,
+    pub questions: Rc<RefCell<Vec<String>>>,
+}
+
+impl ScriptedPrompt {
+    pub fn credentials(self, login: &str, password: &str) -> ScriptedPrompt {
+        self.credentials
+            .borrow_mut()
+            .push_back((login.to_string(), password.to_string()));
+        self
+    }
+
+    pub fn choice(self, choice: usize) -> ScriptedPrompt {
+        self.choices.borrow_mut().push_back(choice);
+        self
+    }
+}
+
+impl Prompt for ScriptedPrompt {
+    fn ask_for_credentials(&self) -> Option<(String, String)> {
+        self.questions.borrow_mut().push("credentials".to_string());
+        self.credentials.borrow_mut().pop_front()
+    }
+
+    fn ask_for_password(&self, login: &str) -> Option<String> {
+        self.questions
+            .borrow_mut()
+            .push(format!("password for {}", login));
+        self.credentials
+            .borrow_mut()
+            .pop_front()
+            .map(|(_, password)| password)
+    }
+
+    fn choose_option(&self, message: &str, _options: &[String]) -> Option<usize> {
+        self.questions.borrow_mut().push(message.to_string());
+        self.choices.borrow_mut().pop_front()
+    }
+}
+
+/// Records every call as `<what>: ok` or `<what>: <error>`
+#[derive(Default)]
+pub struct CapturingDisplay {
+    pub events: Rc<RefCell<Vec<String>>>,
+}
+
+impl CapturingDisplay {
+    fn record<T>(&self, what: &str, result: &SatoriResult<T>) {
+        let event = match result {
+            Ok(_) => format!("{}: ok", what),
+            Err(error) => format!("{}: {:?}", what, error),
+        };
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl SatoriDisplay for CapturingDisplay {
+    fn display_username(&self, username: &SatoriResult<String>) {
+        self.record("username", username);
+    }
+
+    fn display_contests(&self, contests: &SatoriResult<Vec<Contest>>) {
+        self.record("contests", contests);
+    }
+
+    fn display_details(&self, details: &SatoriResult<ResultDetails>) {
+        self.record("details", details);
+    }
+
+    fn display_login(&self, login: &SatoriResult<Session>) {
+        self.record("login", login);
+    }
+
+    fn display_logout(&self, logout: &SatoriResult<()>) {
+        self.record("logout", logout);
+    }
+
+    fn display_problems(&self, problems: &SatoriResult<Vec<Problem>>) {
+        self.record("problems", problems);
+    }
+
+    fn display_pdf(&self, pdf: &SatoriResult<String>) {
+        self.record("pdf", pdf);
+    }
+
+    fn display_results(&self, results: &SatoriResult<Vec<ShortResult>>) {
+        self.record("results", results);
+    }
+
+    fn display_status(&self, status: &SatoriResult<ProblemStatus>) {
+        self.record("status", status);
+    }
+
+    fn display_submit(&self, submit: &SatoriResult<Submission>) {
+        self.record("submit", submit);
+    }
+
+    fn display_test_results(&self, report: &SatoriResult<LocalTestReport>) {
+        self.record("test", report);
+    }
+
+    fn display_waiting(&self, _submission: &str) {}
+
+    fn display_waiting_done(&self) {}
+
+    fn display_watch(&self, details: &SatoriResult<ResultDetails>) {
+        self.record("watch", details);
+    }
+
+    fn display_error(&self, error: &SatoriError) {
+        self.events.borrow_mut().push(format!("error: {:?}", error));
+    }
+}
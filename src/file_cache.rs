@@ -0,0 +1,61 @@
+use crate::cache::{Cache, CacheKind};
+
+use std::{path::PathBuf, time::SystemTime};
+
+pub struct FileCache {
+    directory: PathBuf,
+}
+
+impl FileCache {
+    pub fn new(directory: &str) -> FileCache {
+        FileCache {
+            directory: PathBuf::from(shellexpand::tilde(directory).to_string()),
+        }
+    }
+
+    fn entry_path(&self, kind: CacheKind, key: &str) -> PathBuf {
+        // Keys contain query strings, keep them safe as file names
+        let key = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        self.directory
+            .join(kind.name())
+            .join(format!("{}.json", key))
+    }
+}
+
+impl Cache for FileCache {
+    fn load(&self, kind: CacheKind, key: &str) -> Option<String> {
+        let path = self.entry_path(kind, key);
+        if let Some(ttl) = kind.ttl() {
+            let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+            let age = SystemTime::now().duration_since(modified).ok()?;
+            if age > ttl {
+                return None;
+            }
+        }
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn load_stale(&self, kind: CacheKind, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.entry_path(kind, key)).ok()
+    }
+
+    fn store(&self, kind: CacheKind, key: &str, value: &str) {
+        // Caching is best effort, a failed write only costs another request later
+        let path = self.entry_path(kind, key);
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(path, value);
+    }
+
+    fn invalidate(&self, kind: CacheKind) {
+        let _ = std::fs::remove_dir_all(self.directory.join(kind.name()));
+    }
+
+    fn clear(&self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
@@ -1,31 +1,61 @@
 use crate::token_storage::TokenStorage;
 
 use std::{
-    fs::File,
+    fs::{File, OpenOptions},
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
+
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+
 pub struct FileTokenStorage {
     path: PathBuf,
 }
 
 impl FileTokenStorage {
-    const DEFAULT_TOKEN_PATH: &str = "~/.local/share/satori-cli/token.txt";
-
-    pub fn default() -> FileTokenStorage {
-        return FileTokenStorage::new(FileTokenStorage::DEFAULT_TOKEN_PATH);
-    }
-
     pub fn new(path: &str) -> FileTokenStorage {
         FileTokenStorage {
             path: PathBuf::from(shellexpand::tilde(path).to_string()),
         }
     }
+
+    fn create_parent(&self) -> std::io::Result<()> {
+        let parent = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => return Ok(()),
+        };
+
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        builder.mode(0o700);
+        builder.create(parent)
+    }
+
+    #[cfg(unix)]
+    fn warn_if_permissive(path: &Path) {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            let mode = metadata.permissions().mode();
+            if mode & 0o077 != 0 {
+                eprintln!(
+                    "Warning: {} is accessible by other users (mode {:o}), run `chmod 600 {}`",
+                    path.display(),
+                    mode & 0o777,
+                    path.display()
+                );
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn warn_if_permissive(_path: &Path) {}
 }
 
 impl TokenStorage for FileTokenStorage {
     fn load_token(&self) -> Option<String> {
         let mut file = File::open(&self.path).ok()?;
+        FileTokenStorage::warn_if_permissive(&self.path);
         let mut token = String::new();
         file.read_to_string(&mut token).ok()?;
         match token.len() {
@@ -34,13 +64,31 @@ impl TokenStorage for FileTokenStorage {
         }
     }
 
-    fn save_token(&self, token: &str) {
-        let mut file = File::create(&self.path).unwrap();
-        file.write_all(token.as_bytes()).unwrap();
-        println!("Token saved to {}", self.path.display());
+    fn save_token(&self, token: &str) -> std::io::Result<()> {
+        self.create_parent()?;
+
+        // Write to a temporary file first so an interrupted write never leaves a truncated token
+        let temporary_path = self.path.with_extension("tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(&temporary_path)?;
+        #[cfg(unix)]
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(token.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temporary_path, &self.path)?;
+
+        eprintln!("Token saved to {}", self.path.display());
+        Ok(())
     }
 
-    fn clear_token(&self) {
-        std::fs::remove_file(&self.path);
+    fn clear_token(&self) -> std::io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
     }
 }
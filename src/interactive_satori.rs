@@ -1,29 +1,34 @@
+// Every method ends with an explicit `return` of what it displayed
+#![allow(clippy::needless_return)]
+
 use crate::display::*;
 use crate::prompt::*;
 use crate::satori::*;
 
+use std::time::Duration;
+
 pub struct InteractiveSatori<S: Satori, D: SatoriDisplay, P: Prompt> {
     satori: S,
     display: D,
     prompt: P,
+    login_attempts: u32,
 }
 
 impl<S: Satori, D: SatoriDisplay, P: Prompt> InteractiveSatori<S, D, P> {
-    pub fn new(satori: S, display: D, prompt: P) -> Self {
+    pub fn new(satori: S, display: D, prompt: P, login_attempts: u32) -> Self {
         Self {
             satori,
             display,
             prompt,
+            login_attempts,
         }
     }
 
-    fn log_in(&self) -> SatoriResult<String> {
+    fn log_in(&self) -> SatoriResult<Session> {
         let credentials = self.prompt.ask_for_credentials();
         match credentials {
-            None => {
-                self.display.display_error(&SatoriError::LoginFailed);
-                return Err(SatoriError::LoginFailed);
-            }
+            // No way to get credentials, retrying would not help
+            None => return Err(SatoriError::NotLoggedIn),
             Some((login, password)) => {
                 return self.satori.login(&login, &password);
             }
@@ -79,16 +84,29 @@ impl<S: Satori, D: SatoriDisplay, P: Prompt> InteractiveSatori<S, D, P> {
 }
 
 macro_rules! repeat_until_logged_in {
-    ($self:ident, $action:expr) => {
+    ($self:ident, $action:expr) => {{
+        let mut failed_attempts = 0;
+        let mut logged_in = false;
         loop {
             let result = $action;
             match result {
+                // Fresh session is still rejected, logging in again would not help
+                Err(SatoriError::NotLoggedIn) if logged_in => {
+                    break Err(SatoriError::SessionRejected);
+                }
+                Err(SatoriError::NotLoggedIn) if failed_attempts >= $self.login_attempts => {
+                    break Err(SatoriError::LoginAttemptsExceeded(failed_attempts));
+                }
                 Err(SatoriError::NotLoggedIn) => {
                     let result = $self.log_in();
                     match result {
-                        Ok(_) => continue,
+                        Ok(_) => {
+                            logged_in = true;
+                            continue;
+                        }
                         Err(SatoriError::LoginFailed) => {
                             $self.display.display_error(&SatoriError::LoginFailed);
+                            failed_attempts += 1;
                             continue;
                         }
                         Err(error) => {
@@ -101,7 +119,7 @@ macro_rules! repeat_until_logged_in {
                 }
             }
         }
-    };
+    }};
 }
 
 impl<S: Satori, D: SatoriDisplay, P: Prompt> Satori for InteractiveSatori<S, D, P> {
@@ -124,7 +142,16 @@ impl<S: Satori, D: SatoriDisplay, P: Prompt> Satori for InteractiveSatori<S, D,
         return details;
     }
 
-    fn login(&self, login: &str, password: &str) -> SatoriResult<String> {
+    fn dump_page(&self, path: &str) -> SatoriResult<String> {
+        // Logged out pages are worth capturing too, so no login here
+        let page = self.satori.dump_page(path);
+        if let Err(error) = &page {
+            self.display.display_error(error);
+        }
+        return page;
+    }
+
+    fn login(&self, login: &str, password: &str) -> SatoriResult<Session> {
         let result = self.satori.login(login, password);
         self.display.display_login(&result);
         return result;
@@ -149,8 +176,19 @@ impl<S: Satori, D: SatoriDisplay, P: Prompt> Satori for InteractiveSatori<S, D,
         return problems;
     }
 
-    fn pdf(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<()> {
-        let pdf = repeat_until_logged_in!(self, self.satori.pdf(contest, problem, force));
+    fn pdf(&self, contest: &str, problem: &str, output: &str, force: bool) -> SatoriResult<String> {
+        let pdf = repeat_until_logged_in!(self, self.satori.pdf(contest, problem, output, force));
+        let pdf = match pdf {
+            Err(SatoriError::AmbiguousContest(error)) => match self.disambiguate_contest(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(contest) => return self.pdf(&contest.id, problem, output, force),
+            },
+            Err(SatoriError::AmbiguousProblem(error)) => match self.disambiguate_problem(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(problem) => return self.pdf(contest, &problem.code, output, force),
+            },
+            result => result,
+        };
         self.display.display_pdf(&pdf);
         return pdf;
     }
@@ -174,7 +212,7 @@ impl<S: Satori, D: SatoriDisplay, P: Prompt> Satori for InteractiveSatori<S, D,
             },
             Err(SatoriError::AmbiguousProblem(error)) => match self.disambiguate_problem(&error) {
                 None => Err(SatoriError::InvalidChoice),
-                Some(problem) => self.results(contest, Some(&problem.id), limit, force),
+                Some(problem) => return self.results(contest, Some(&problem.code), limit, force),
             },
             result => result,
         };
@@ -183,15 +221,275 @@ impl<S: Satori, D: SatoriDisplay, P: Prompt> Satori for InteractiveSatori<S, D,
         return results;
     }
 
-    fn status(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<String> {
-        let status = repeat_until_logged_in!(self, self.satori.status(contest, problem, force));
+    fn status(
+        &self,
+        contest: &str,
+        problem: &str,
+        mode: StatusMode,
+        force: bool,
+    ) -> SatoriResult<ProblemStatus> {
+        let status =
+            repeat_until_logged_in!(self, self.satori.status(contest, problem, mode, force));
+        let status = match status {
+            Err(SatoriError::AmbiguousContest(error)) => match self.disambiguate_contest(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(contest) => return self.status(&contest.id, problem, mode, force),
+            },
+            Err(SatoriError::AmbiguousProblem(error)) => match self.disambiguate_problem(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(problem) => return self.status(contest, &problem.code, mode, force),
+            },
+            result => result,
+        };
         self.display.display_status(&status);
         return status;
     }
 
-    fn submit(&self, contest: &str, problem: &str, file_path: &str) -> SatoriResult<()> {
-        let submit = repeat_until_logged_in!(self, self.satori.submit(contest, problem, file_path));
+    fn submit(
+        &self,
+        contest: &str,
+        problem: &str,
+        file_path: &str,
+        language: Option<&str>,
+    ) -> SatoriResult<Submission> {
+        let submit = repeat_until_logged_in!(
+            self,
+            self.satori.submit(contest, problem, file_path, language)
+        );
+        let submit = match submit {
+            Err(SatoriError::AmbiguousContest(error)) => match self.disambiguate_contest(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(contest) => return self.submit(&contest.id, problem, file_path, language),
+            },
+            Err(SatoriError::AmbiguousProblem(error)) => match self.disambiguate_problem(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(problem) => return self.submit(contest, &problem.code, file_path, language),
+            },
+            result => result,
+        };
         self.display.display_submit(&submit);
         return submit;
     }
+
+    fn watch(
+        &self,
+        contest: &str,
+        submission: &str,
+        timeout: Duration,
+    ) -> SatoriResult<ResultDetails> {
+        let details = repeat_until_logged_in!(self, {
+            // Spinner is stopped before any prompt is shown
+            self.display.display_waiting(submission);
+            let details = self.satori.watch(contest, submission, timeout);
+            self.display.display_waiting_done();
+            details
+        });
+        let details = match details {
+            Err(SatoriError::AmbiguousContest(error)) => match self.disambiguate_contest(&error) {
+                None => Err(SatoriError::InvalidChoice),
+                Some(contest) => return self.watch(&contest.id, submission, timeout),
+            },
+            result => result,
+        };
+        self.display.display_watch(&details);
+        return details;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes::*;
+    use crate::simple_satori::SimpleSatori;
+    use crate::soup_parser::SoupParser;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type FakeSatori = SimpleSatori<FakeClient, SoupParser, MemoryTokenStorage, MemoryCache>;
+
+    struct Harness {
+        satori: InteractiveSatori<FakeSatori, CapturingDisplay, ScriptedPrompt>,
+        events: Rc<RefCell<Vec<String>>>,
+        questions: Rc<RefCell<Vec<String>>>,
+    }
+
+    fn harness(token: MemoryTokenStorage, prompt: ScriptedPrompt) -> Harness {
+        harness_with_client(FakeClient::new(), token, prompt)
+    }
+
+    fn harness_with_client(
+        client: FakeClient,
+        token: MemoryTokenStorage,
+        prompt: ScriptedPrompt,
+    ) -> Harness {
+        let satori = SimpleSatori::new(client, SoupParser::new(), token, MemoryCache::default());
+        let display = CapturingDisplay::default();
+        let events = display.events.clone();
+        let questions = prompt.questions.clone();
+        Harness {
+            satori: InteractiveSatori::new(satori, display, prompt, 3),
+            events,
+            questions,
+        }
+    }
+
+    #[test]
+    fn logs_in_when_token_expired() {
+        let prompt = ScriptedPrompt::default().credentials(LOGIN, PASSWORD);
+        let harness = harness(MemoryTokenStorage::with_token("expired-token"), prompt);
+
+        assert!(harness.satori.contests(false, false).is_ok());
+        assert_eq!(*harness.questions.borrow(), vec!["credentials"]);
+        assert_eq!(*harness.events.borrow(), vec!["contests: ok"]);
+    }
+
+    #[test]
+    fn retries_login_after_wrong_password() {
+        let prompt = ScriptedPrompt::default()
+            .credentials(LOGIN, "wrong")
+            .credentials(LOGIN, PASSWORD);
+        let harness = harness(MemoryTokenStorage::default(), prompt);
+
+        assert!(harness.satori.contests(false, false).is_ok());
+        assert_eq!(
+            *harness.events.borrow(),
+            vec!["error: LoginFailed", "contests: ok"]
+        );
+    }
+
+    #[test]
+    fn gives_up_after_login_attempts() {
+        let prompt = ScriptedPrompt::default()
+            .credentials(LOGIN, "wrong")
+            .credentials(LOGIN, "wrong")
+            .credentials(LOGIN, "wrong")
+            .credentials(LOGIN, PASSWORD);
+        let harness = harness(MemoryTokenStorage::default(), prompt);
+
+        assert!(matches!(
+            harness.satori.contests(false, false),
+            Err(SatoriError::LoginAttemptsExceeded(3))
+        ));
+        assert_eq!(harness.questions.borrow().len(), 3);
+        assert_eq!(
+            harness.events.borrow().last().unwrap(),
+            "contests: LoginAttemptsExceeded(3)"
+        );
+    }
+
+    #[test]
+    fn fails_without_credentials() {
+        let harness = harness(MemoryTokenStorage::default(), ScriptedPrompt::default());
+
+        assert!(matches!(
+            harness.satori.contests(false, false),
+            Err(SatoriError::NotLoggedIn)
+        ));
+        assert_eq!(*harness.events.borrow(), vec!["contests: NotLoggedIn"]);
+    }
+
+    #[test]
+    fn disambiguates_contest() {
+        let prompt = ScriptedPrompt::default().choice(0);
+        let harness = harness(MemoryTokenStorage::with_token(TOKEN), prompt);
+
+        let problems = harness.satori.problems("5", false).unwrap();
+        assert_eq!(problems[0].contest_id, "5241304");
+        assert_eq!(
+            *harness.questions.borrow(),
+            vec!["Contest 5 is ambiguous. Please choose one:"]
+        );
+        assert_eq!(*harness.events.borrow(), vec!["problems: ok"]);
+    }
+
+    #[test]
+    fn disambiguates_problem() {
+        let prompt = ScriptedPrompt::default().choice(0);
+        let harness = harness(MemoryTokenStorage::with_token(TOKEN), prompt);
+
+        assert_eq!(
+            harness
+                .satori
+                .submit("Algo", "S", "Cargo.toml", Some("C"))
+                .unwrap()
+                .submission_id,
+            "8812345"
+        );
+        assert_eq!(
+            *harness.questions.borrow(),
+            vec!["Problem S is ambiguous. Please choose one:"]
+        );
+        assert_eq!(*harness.events.borrow(), vec!["submit: ok"]);
+    }
+
+    #[test]
+    fn disambiguates_closed_problem() {
+        let client = FakeClient::new().route(
+            "/contest/5241304/results?results_limit=100&results_page=1",
+            fixture!("results"),
+        );
+        let prompt = ScriptedPrompt::default().choice(1);
+        let harness = harness_with_client(client, MemoryTokenStorage::with_token(TOKEN), prompt);
+
+        let status = harness
+            .satori
+            .status("Algo", "S", StatusMode::Recent, false)
+            .unwrap();
+        assert_eq!(status.problem_code, "B");
+        assert_eq!(
+            *harness.questions.borrow(),
+            vec!["Problem S is ambiguous. Please choose one:"]
+        );
+        assert_eq!(*harness.events.borrow(), vec!["status: ok"]);
+    }
+
+    #[test]
+    fn disambiguated_results_are_displayed_once() {
+        let client = FakeClient::new().route(
+            "/contest/5241304/results?results_filter_problem=5241320",
+            fixture!("results"),
+        );
+        let prompt = ScriptedPrompt::default().choice(0);
+        let harness = harness_with_client(client, MemoryTokenStorage::with_token(TOKEN), prompt);
+
+        let results = harness
+            .satori
+            .results("Algo", Some("S"), None, false)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            *harness.questions.borrow(),
+            vec!["Problem S is ambiguous. Please choose one:"]
+        );
+        assert_eq!(*harness.events.borrow(), vec!["results: ok"]);
+    }
+
+    #[test]
+    fn invalid_choice_is_reported() {
+        let harness = harness(
+            MemoryTokenStorage::with_token(TOKEN),
+            ScriptedPrompt::default(),
+        );
+
+        assert!(matches!(
+            harness.satori.problems("5", false),
+            Err(SatoriError::InvalidChoice)
+        ));
+        assert_eq!(*harness.events.borrow(), vec!["problems: InvalidChoice"]);
+    }
+
+    #[test]
+    fn connection_error_is_displayed() {
+        let harness = harness(
+            MemoryTokenStorage::with_token(TOKEN),
+            ScriptedPrompt::default(),
+        );
+
+        assert!(harness.satori.results("Algo", None, None, false).is_err());
+        assert_eq!(
+            *harness.events.borrow(),
+            vec!["results: ConnectionFailed(HttpStatus(404))"]
+        );
+    }
 }
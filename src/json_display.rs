@@ -0,0 +1,129 @@
+use crate::display::*;
+use crate::satori::*;
+use crate::test_runner::LocalTestReport;
+
+use serde::Serialize;
+use serde_json::json;
+use std::cell::RefCell;
+
+/// Prints every result as a single line of JSON, errors as `{"error": ..., "details": ...}`
+pub struct JsonDisplay {
+    /// Whether a submission is held back to be printed together with its verdict
+    combine_submit: bool,
+    submission: RefCell<Option<serde_json::Value>>,
+}
+impl JsonDisplay {
+    pub fn new() -> JsonDisplay {
+        JsonDisplay {
+            combine_submit: false,
+            submission: RefCell::new(None),
+        }
+    }
+
+    /// For `submit --wait`, which prints one `{"submission": ..., "result": ...}` object
+    pub fn combining_submit() -> JsonDisplay {
+        JsonDisplay {
+            combine_submit: true,
+            submission: RefCell::new(None),
+        }
+    }
+
+    fn print<T: Serialize>(&self, value: &T) {
+        match serde_json::to_string(value) {
+            Ok(json) => println!("{}", json),
+            Err(error) => eprintln!("Serialization failed: {}", error),
+        }
+    }
+
+    fn print_result<T: Serialize, E: Serialize>(&self, result: &Result<T, E>) {
+        match result {
+            Ok(value) => self.print(value),
+            Err(error) => self.print(error),
+        }
+    }
+}
+
+impl SatoriDisplay for JsonDisplay {
+    fn display_username(&self, username: &SatoriResult<String>) {
+        self.print_result(
+            &username
+                .as_ref()
+                .map(|username| json!({ "username": username })),
+        );
+    }
+
+    fn display_contests(&self, contests: &SatoriResult<Vec<Contest>>) {
+        self.print_result(contests);
+    }
+
+    fn display_details(&self, details: &SatoriResult<ResultDetails>) {
+        self.print_result(details);
+    }
+
+    fn display_login(&self, login: &SatoriResult<Session>) {
+        self.print_result(login);
+    }
+
+    fn display_logout(&self, logout: &SatoriResult<()>) {
+        self.print_result(&logout.as_ref().map(|_| json!({ "logged_out": true })));
+    }
+
+    fn display_problems(&self, problems: &SatoriResult<Vec<Problem>>) {
+        self.print_result(problems);
+    }
+
+    fn display_pdf(&self, pdf: &SatoriResult<String>) {
+        self.print_result(&pdf.as_ref().map(|path| json!({ "path": path })));
+    }
+
+    fn display_results(&self, results: &SatoriResult<Vec<ShortResult>>) {
+        self.print_result(results);
+    }
+
+    fn display_status(&self, status: &SatoriResult<ProblemStatus>) {
+        self.print_result(status);
+    }
+
+    fn display_submit(&self, submit: &SatoriResult<Submission>) {
+        match submit {
+            Ok(submission) if self.combine_submit => {
+                *self.submission.borrow_mut() = serde_json::to_value(submission).ok();
+            }
+            _ => self.print_result(submit),
+        }
+    }
+
+    fn display_test_results(&self, report: &SatoriResult<LocalTestReport>) {
+        self.print_result(report);
+    }
+
+    fn display_waiting(&self, _submission: &str) {}
+
+    fn display_waiting_done(&self) {}
+
+    fn display_watch(&self, details: &SatoriResult<ResultDetails>) {
+        match self.submission.take() {
+            Some(submission) => {
+                let result = match details {
+                    Ok(details) => serde_json::to_value(details),
+                    Err(error) => serde_json::to_value(error),
+                };
+                match result {
+                    Ok(result) => {
+                        self.print(&json!({ "submission": submission, "result": result }))
+                    }
+                    Err(error) => eprintln!("Serialization failed: {}", error),
+                }
+            }
+            None => self.print_result(details),
+        }
+    }
+
+    /// Errors the command goes on after go to stderr, so stdout holds only its one result
+    fn display_error(&self, error: &SatoriError) {
+        match serde_json::to_string(error) {
+            Ok(json) => eprintln!("{}", json),
+            Err(error) => eprintln!("Serialization failed: {}", error),
+        }
+    }
+}
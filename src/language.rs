@@ -0,0 +1,74 @@
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    C,
+    Cpp,
+    Java,
+    Python,
+    Rust,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Option<Language> {
+        match path.extension()?.to_str()? {
+            "c" => Some(Language::C),
+            "cpp" | "cc" | "cxx" => Some(Language::Cpp),
+            "java" => Some(Language::Java),
+            "py" => Some(Language::Python),
+            "rs" => Some(Language::Rust),
+            _ => None,
+        }
+    }
+
+    /// Name matched against the languages offered by the submit form
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::Java => "Java",
+            Language::Python => "Python",
+            Language::Rust => "Rust",
+        }
+    }
+
+    /// Command that compiles `source` into `build_dir`, `None` for interpreted languages
+    pub fn compile_command(&self, source: &Path, build_dir: &Path) -> Option<Command> {
+        let binary = build_dir.join("solution");
+        let mut command = match self {
+            Language::C => Command::new("gcc"),
+            Language::Cpp => Command::new("g++"),
+            Language::Java => Command::new("javac"),
+            Language::Rust => Command::new("rustc"),
+            Language::Python => return None,
+        };
+        match self {
+            Language::Cpp => command.args(["-O2", "-std=c++17", "-o"]).arg(binary),
+            Language::Java => command.arg("-d").arg(build_dir),
+            Language::Rust => command.args(["-O", "-o"]).arg(binary),
+            _ => command.args(["-O2", "-o"]).arg(binary),
+        };
+        command.arg(source);
+        Some(command)
+    }
+
+    /// Command that runs the solution compiled by `compile_command`
+    pub fn run_command(&self, source: &Path, build_dir: &Path) -> Command {
+        match self {
+            Language::Python => {
+                let mut command = Command::new("python3");
+                command.arg(source);
+                command
+            }
+            Language::Java => {
+                // Class name has to match the file name
+                let class = source.file_stem().unwrap_or_default();
+                let mut command = Command::new("java");
+                command.arg("-cp").arg(build_dir).arg(class);
+                command
+            }
+            _ => Command::new(build_dir.join("solution")),
+        }
+    }
+}
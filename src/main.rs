@@ -1,148 +1,341 @@
+mod cache;
 mod cli;
+mod completion;
+mod config;
 mod display;
+mod encrypted_token_storage;
+mod exit_code;
+#[cfg(test)]
+mod fakes;
+mod file_cache;
 mod file_token_storage;
 mod interactive_satori;
+mod json_display;
+mod language;
 mod parser;
+mod profile;
 mod prompt;
 mod reqwest_satori_client;
 mod satori;
 mod satori_client;
 mod simple_display;
+mod simple_prompt;
 mod simple_satori;
 mod soup_parser;
+mod test_runner;
 mod token_storage;
 
+use crate::config::{Config, TokenStorageKind};
 use crate::display::SatoriDisplay;
+use crate::encrypted_token_storage::{EncryptedTokenStorage, KeySource};
+use crate::file_token_storage::FileTokenStorage;
+use crate::profile::Profiles;
+use crate::prompt::Prompt;
+use crate::reqwest_satori_client::ClientSettings;
 use crate::satori::Satori;
+use crate::simple_prompt::SimplePrompt;
+use crate::token_storage::TokenStorage;
 
-use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
 
-const URL: &str = "https://satori.tcs.uj.edu.pl";
-const TOKEN_NAME: &str = "satori_token";
+const DEFAULT_LOGIN_ATTEMPTS: u32 = 3;
+const DEFAULT_WAIT_TIMEOUT: u64 = 600;
 
-struct SimplePrompt {}
-impl SimplePrompt {
-    pub fn new() -> SimplePrompt {
-        SimplePrompt {}
+fn main() {
+    clap_complete::CompleteEnv::with_factory(cli::build_cli)
+        .var(completion::COMPLETE_VARIABLE)
+        .bin(completion::BIN_NAME)
+        .complete();
+
+    let matches = cli::build_cli().get_matches();
+    if let Some(("completions", args)) = matches.subcommand() {
+        std::process::exit(do_completions(args));
+    }
+    let mut config = std::env::current_dir()
+        .map_err(|error| error.to_string())
+        .and_then(|directory| Config::discover(&directory))
+        .unwrap_or_else(|error| {
+            eprintln!("Invalid configuration: {}", error);
+            std::process::exit(exit_code::CONFIG);
+        });
+    let profiles = Profiles::load().unwrap_or_else(|error| {
+        eprintln!("Invalid profiles: {}", error);
+        std::process::exit(exit_code::CONFIG);
+    });
+
+    if let Some(("profile", args)) = matches.subcommand() {
+        std::process::exit(do_profile(profiles, args));
+    }
+    // Local tests need neither a profile nor a connection
+    if let Some(("test", args)) = matches.subcommand() {
+        let code = match matches.get_one::<String>("format").map(|s| s.as_str()) {
+            Some("json") => do_test(json_display::JsonDisplay::new(), args, &config),
+            _ => do_test(simple_display::SimpleDisplay::new(), args, &config),
+        };
+        std::process::exit(code);
     }
-}
-impl prompt::Prompt for SimplePrompt {
-    fn ask_for_credentials(&self) -> Option<(String, String)> {
-        let mut login = String::new();
 
-        print!("Login: ");
-        std::io::stdout().flush().unwrap();
-        std::io::stdin().read_line(&mut login).unwrap();
-        login.pop(); // remove newline
-        let password = rpassword::prompt_password("Password: ").unwrap();
+    let profile_name = matches
+        .get_one::<String>("profile")
+        .or(config.profile.as_ref())
+        .map(|name| name.as_str())
+        .unwrap_or(profiles.current())
+        .to_string();
+    if let Err(error) = Profiles::validate_name(&profile_name) {
+        eprintln!("{}", error);
+        std::process::exit(exit_code::USAGE);
+    }
+    let profile = profiles.get(&profile_name).unwrap_or_else(|| {
+        eprintln!(
+            "Unknown profile {}, add it with `satori-cli profile add {}`",
+            profile_name, profile_name
+        );
+        std::process::exit(exit_code::USAGE);
+    });
+    if config.contest.is_none() {
+        config.contest = profile.contest;
+    }
 
-        Some((login, password))
+    let settings = client_settings(&matches, &config);
+    let url = config.url(matches.get_one::<String>("url").map(|url| url.as_str()));
+    if !reqwest::Url::parse(&url).is_ok_and(|url| url.host_str().is_some()) {
+        eprintln!("Invalid URL {}", url);
+        std::process::exit(exit_code::USAGE);
     }
+    let instance = Profiles::instance(&url);
+    let client =
+        reqwest_satori_client::ReqwestSatoriClient::new(&url, &config.token_name(), settings);
+    let parser = soup_parser::SoupParser::new();
+    let token_storage = token_storage(
+        &profile_name,
+        instance.as_deref(),
+        &config,
+        !matches.get_flag("non-interactive"),
+    );
+    let cache =
+        file_cache::FileCache::new(&Profiles::cache_path(&profile_name, instance.as_deref()));
+    let satori = simple_satori::SimpleSatori::new(client, parser, token_storage, cache);
+    let prompt = build_prompt(&matches, &config);
+    let login_attempts = config.login_attempts.unwrap_or(DEFAULT_LOGIN_ATTEMPTS);
 
-    fn choose_option(&self, message: &str, options: &Vec<String>) -> Option<usize> {
-        println!("{}", message);
-        for (i, option) in options.iter().enumerate() {
-            println!("{}. {}", i + 1, option);
+    let code = match matches.get_one::<String>("format").map(|s| s.as_str()) {
+        Some("json") => {
+            let submit_wait = matches
+                .subcommand_matches("submit")
+                .is_some_and(|args| args.get_flag("wait"));
+            let display = match submit_wait {
+                true => json_display::JsonDisplay::combining_submit(),
+                false => json_display::JsonDisplay::new(),
+            };
+            let satori =
+                interactive_satori::InteractiveSatori::new(satori, display, prompt, login_attempts);
+            run_app(satori, &matches, &config)
+        }
+        _ => {
+            let display = simple_display::SimpleDisplay::new();
+            let satori =
+                interactive_satori::InteractiveSatori::new(satori, display, prompt, login_attempts);
+            run_app(satori, &matches, &config)
         }
+    };
+    std::process::exit(code);
+}
+
+fn token_storage(
+    profile_name: &str,
+    instance: Option<&str>,
+    config: &Config,
+    interactive: bool,
+) -> Box<dyn TokenStorage> {
+    let path = Profiles::token_path(profile_name, instance);
 
-        let mut choice = String::new();
-        print!("Your choice: ");
-        std::io::stdout().flush().unwrap();
-        std::io::stdin().read_line(&mut choice).unwrap();
-        choice.pop(); // remove newline
-
-        match choice.parse::<usize>() {
-            Ok(choice) => {
-                if choice > 0 && choice <= options.len() {
-                    Some(choice - 1)
-                } else {
-                    None
+    match config.token.storage {
+        Some(TokenStorageKind::Encrypted) => {
+            let key_source = match &config.token.key_file {
+                Some(key_file) => {
+                    KeySource::KeyFile(shellexpand::tilde(key_file).to_string().into())
                 }
-            }
-            Err(_) => None,
+                None => KeySource::Passphrase {
+                    prompt: interactive,
+                },
+            };
+            // Separate file, so switching storage does not try to decrypt a plain token
+            let path = Path::new(&path).with_extension("enc");
+            let inner = FileTokenStorage::new(&path.display().to_string());
+            Box::new(EncryptedTokenStorage::new(inner, key_source))
         }
+        _ => Box::new(FileTokenStorage::new(&path)),
     }
 }
 
-fn main() {
-    let client = reqwest_satori_client::ReqwestSatoriClient::new(URL, TOKEN_NAME);
-    let parser = soup_parser::SoupParser::new();
-    let token_storage = file_token_storage::FileTokenStorage::default();
-    let satori = simple_satori::SimpleSatori::new(client, parser, token_storage);
-    let display = simple_display::SimpleDisplay::new();
-    let prompt = SimplePrompt::new();
+fn build_prompt(matches: &clap::ArgMatches, config: &Config) -> SimplePrompt {
+    SimplePrompt::new(
+        !matches.get_flag("non-interactive"),
+        matches.get_flag("password-stdin"),
+        config.credential_helper.clone(),
+    )
+}
 
-    let satori = interactive_satori::InteractiveSatori::new(satori, display, prompt);
-    run_app(satori);
+/// Defaults overridden by the configuration, overridden by command line flags
+fn client_settings(matches: &clap::ArgMatches, config: &Config) -> ClientSettings {
+    let mut settings = ClientSettings::default();
+    let network = &config.network;
+    if let Some(seconds) = matches
+        .get_one::<u64>("connect-timeout")
+        .or(network.connect_timeout.as_ref())
+    {
+        settings.connect_timeout = Duration::from_secs(*seconds);
+    }
+    if let Some(seconds) = matches
+        .get_one::<u64>("timeout")
+        .or(network.timeout.as_ref())
+    {
+        settings.timeout = Duration::from_secs(*seconds);
+    }
+    if let Some(retries) = matches
+        .get_one::<u32>("retries")
+        .or(network.retries.as_ref())
+    {
+        settings.retries = *retries;
+    }
+    settings
 }
 
-fn run_app(satori: impl Satori) {
-    println!("Satori is fucking slow, please be patient. I can't do anything about it :(");
-    match cli::build_cli().get_matches().subcommand() {
+fn run_app(satori: impl Satori, matches: &clap::ArgMatches, config: &Config) -> i32 {
+    eprintln!("Satori is fucking slow, please be patient. I can't do anything about it :(");
+    match matches.subcommand() {
         Some((cmd, args)) => match cmd {
+            "login" => do_login(satori, args, build_prompt(matches, config)),
             "username" => do_username(satori, args),
             "contests" => do_contests(satori, args),
-            "details" => do_details(satori, args),
+            "details" => do_details(satori, args, config),
             "logout" => do_logout(satori, args),
-            "problems" => do_problems(satori, args),
-            "pdf" => do_pdf(satori, args),
-            "results" => do_results(satori, args),
-            "status" => do_status(satori, args),
-            "submit" => do_submit(satori, args),
-            _ => println!("Unknown command"),
+            "problems" => do_problems(satori, args, config),
+            "pdf" => do_pdf(satori, args, config),
+            "results" => do_results(satori, args, config),
+            "status" => do_status(satori, args, config),
+            "submit" => do_submit(satori, args, config),
+            "watch" => do_watch(satori, args, config),
+            "debug" => do_debug(satori, args),
+            _ => {
+                println!("Unknown command");
+                exit_code::USAGE
+            }
         },
 
-        _ => println!("Oops, something went terribly wrong."),
+        _ => {
+            println!("Oops, something went terribly wrong.");
+            exit_code::USAGE
+        }
+    }
+}
+
+/// Value of an argument, falling back to the configuration when it is not given
+fn arg_or_config<'a>(
+    args: &'a clap::ArgMatches,
+    name: &str,
+    default: &'a Option<String>,
+) -> &'a str {
+    match args.get_one::<String>(name).or(default.as_ref()) {
+        Some(value) => value,
+        None => cli::build_cli()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                format!(
+                    "--{} is required, pass it or set `{}` in satori-cli.toml",
+                    name, name
+                ),
+            )
+            .exit(),
+    }
+}
+
+fn do_login(satori: impl Satori, args: &clap::ArgMatches, prompt: impl Prompt) -> i32 {
+    let credentials = match args.get_one::<String>("user") {
+        Some(login) => prompt
+            .ask_for_password(login)
+            .map(|password| (login.clone(), password)),
+        None => prompt.ask_for_credentials(),
+    };
+
+    match credentials {
+        Some((login, password)) => exit_code::from_result(&satori.login(&login, &password)),
+        None => {
+            eprintln!("No credentials given.");
+            exit_code::NOT_LOGGED_IN
+        }
     }
 }
 
 #[allow(unused)]
-fn do_username(satori: impl Satori, args: &clap::ArgMatches) {
-    satori.username();
+fn do_username(satori: impl Satori, args: &clap::ArgMatches) -> i32 {
+    exit_code::from_result(&satori.username())
 }
 
 #[allow(unused)]
-fn do_contests(satori: impl Satori, args: &clap::ArgMatches) {
+fn do_contests(satori: impl Satori, args: &clap::ArgMatches) -> i32 {
     let archived = args.get_flag("archived");
     let force = args.get_flag("force");
 
-    satori.contests(archived, force);
+    exit_code::from_result(&satori.contests(archived, force))
 }
 
 #[allow(unused)]
-fn do_details(satori: impl Satori, args: &clap::ArgMatches) {
-    let contest = args.get_one::<String>("contest").unwrap();
+fn do_details(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
     let submission = args.get_one::<String>("submission").unwrap();
     let force = args.get_flag("force");
 
-    satori.details(contest, submission, force);
+    let details = satori.details(contest, submission, force);
+    exit_code::from_verdict_result(&details, |details| Some(&details.status))
 }
 
 #[allow(unused)]
-fn do_logout(satori: impl Satori, _args: &clap::ArgMatches) {
-    satori.logout();
+fn do_logout(satori: impl Satori, _args: &clap::ArgMatches) -> i32 {
+    exit_code::from_result(&satori.logout())
 }
 
 #[allow(unused)]
-fn do_problems(satori: impl Satori, args: &clap::ArgMatches) {
-    let contest = args.get_one::<String>("contest").unwrap();
+fn do_problems(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
     let force = args.get_flag("force");
 
-    satori.problems(contest, force);
+    exit_code::from_result(&satori.problems(contest, force))
 }
 
 #[allow(unused)]
-fn do_pdf(satori: impl Satori, args: &clap::ArgMatches) {
-    let contest = args.get_one::<String>("contest").unwrap();
-    let problem = args.get_one::<String>("problem").unwrap();
+fn do_pdf(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
+    let problem = arg_or_config(args, "problem", &config.problem);
+    let output = args.get_one::<String>("output").unwrap();
+    let open = args.get_flag("open");
     let force = args.get_flag("force");
 
-    satori.pdf(contest, problem, force);
+    let pdf = satori.pdf(contest, problem, output, force);
+    if let (Ok(path), true) = (&pdf, open) {
+        open_file(path);
+    }
+    exit_code::from_result(&pdf)
+}
+
+fn open_file(path: &str) {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+
+    if let Err(error) = std::process::Command::new(opener).arg(path).spawn() {
+        eprintln!("Failed to open {}: {}", path, error);
+    }
 }
 
 #[allow(unused)]
-fn do_results(satori: impl Satori, args: &clap::ArgMatches) {
-    let contest = args.get_one::<String>("contest").unwrap();
+fn do_results(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
     let default_problem = String::new();
     let problem = args.get_one::<String>("problem").map(|s| &**s);
     let limit = args
@@ -150,23 +343,184 @@ fn do_results(satori: impl Satori, args: &clap::ArgMatches) {
         .map(|s| s.parse::<usize>().unwrap_or(10));
     let force = args.get_flag("force");
 
-    satori.results(contest, problem, limit, force);
+    exit_code::from_result(&satori.results(contest, problem, limit, force))
 }
 
 #[allow(unused)]
-fn do_status(satori: impl Satori, args: &clap::ArgMatches) {
-    let contest = args.get_one::<String>("contest").unwrap();
-    let problem = args.get_one::<String>("problem").unwrap();
+fn do_status(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
+    let problem = arg_or_config(args, "problem", &config.problem);
     let force = args.get_flag("force");
+    let mode = match args.get_flag("recent") {
+        true => satori::StatusMode::Recent,
+        false => satori::StatusMode::Best,
+    };
+
+    let status = satori.status(contest, problem, mode, force);
+    exit_code::from_verdict_result(&status, |status| {
+        status.result.as_ref().map(|result| &result.status)
+    })
+}
+
+#[allow(unused)]
+fn do_submit(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
+    let problem = arg_or_config(args, "problem", &config.problem);
+    let file = arg_or_config(args, "file", &config.file);
+    let language = args.get_one::<String>("lang").map(|s| s.as_str());
+    let wait = args.get_flag("wait");
 
-    satori.status(contest, problem, force);
+    let submission = satori.submit(contest, problem, file, language);
+    match (&submission, wait) {
+        (Ok(submission), true) => {
+            let details = satori.watch(
+                &submission.contest_id,
+                &submission.submission_id,
+                wait_timeout(args, config),
+            );
+            exit_code::from_verdict_result(&details, |details| Some(&details.status))
+        }
+        _ => exit_code::from_result(&submission),
+    }
 }
 
 #[allow(unused)]
-fn do_submit(satori: impl Satori, args: &clap::ArgMatches) {
-    let contest = args.get_one::<String>("contest").unwrap();
-    let problem = args.get_one::<String>("problem").unwrap();
-    let file = args.get_one::<String>("file").unwrap();
+fn do_watch(satori: impl Satori, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let contest = arg_or_config(args, "contest", &config.contest);
+    let submission = args.get_one::<String>("submission").unwrap();
+
+    let details = satori.watch(contest, submission, wait_timeout(args, config));
+    exit_code::from_verdict_result(&details, |details| Some(&details.status))
+}
+
+fn wait_timeout(args: &clap::ArgMatches, config: &Config) -> Duration {
+    let seconds = args
+        .get_one::<u64>("wait-timeout")
+        .or(config.wait_timeout.as_ref())
+        .unwrap_or(&DEFAULT_WAIT_TIMEOUT);
+    Duration::from_secs(*seconds)
+}
 
-    satori.submit(contest, problem, file);
+fn do_debug(satori: impl Satori, args: &clap::ArgMatches) -> i32 {
+    match args.subcommand() {
+        Some(("dump-page", args)) => {
+            let path = args.get_one::<String>("path").unwrap();
+            let page = match satori.dump_page(path) {
+                Ok(page) => page,
+                Err(error) => return exit_code::from_error(&error),
+            };
+            match args.get_one::<String>("output") {
+                None => {
+                    print!("{}", page);
+                    exit_code::SUCCESS
+                }
+                Some(output) => match std::fs::write(output, page) {
+                    Ok(()) => exit_code::SUCCESS,
+                    Err(error) => {
+                        eprintln!("Cannot write {}: {}", output, error);
+                        exit_code::FILE_ERROR
+                    }
+                },
+            }
+        }
+        _ => exit_code::USAGE,
+    }
+}
+
+fn do_test(display: impl SatoriDisplay, args: &clap::ArgMatches, config: &Config) -> i32 {
+    let problem = arg_or_config(args, "problem", &config.problem);
+    let file = arg_or_config(args, "file", &config.file);
+    let time_limit = *args.get_one::<Duration>("time-limit").unwrap();
+
+    let tests_dir = match args.get_one::<String>("tests") {
+        Some(tests_dir) => Path::new(tests_dir).to_path_buf(),
+        None => {
+            let tests_dir = Path::new(file)
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join("tests");
+            match tests_dir.join(problem).is_dir() {
+                true => tests_dir.join(problem),
+                false => tests_dir,
+            }
+        }
+    };
+
+    let runner = test_runner::TestRunner::new(time_limit);
+    let report = runner.run(problem, file, &tests_dir);
+    display.display_test_results(&report);
+    exit_code::from_verdict_result(&report, |report| Some(&report.status))
+}
+
+fn do_completions(args: &clap::ArgMatches) -> i32 {
+    let shell = args.get_one::<String>("shell").unwrap();
+    match completion::write_registration(shell, &mut std::io::stdout()) {
+        Ok(()) => exit_code::SUCCESS,
+        Err(error) => {
+            eprintln!("Cannot print completions: {}", error);
+            exit_code::FILE_ERROR
+        }
+    }
+}
+
+fn do_profile(mut profiles: Profiles, args: &clap::ArgMatches) -> i32 {
+    let result = match args.subcommand() {
+        Some(("list", _)) => {
+            for name in profiles.names() {
+                let current = if name == profiles.current() { "*" } else { " " };
+                match profiles.get(&name).and_then(|profile| profile.contest) {
+                    Some(contest) => println!("{} {} (contest: {})", current, name, contest),
+                    None => println!("{} {}", current, name),
+                }
+            }
+            return exit_code::SUCCESS;
+        }
+        Some(("add", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+            let contest = args.get_one::<String>("contest").cloned();
+            Profiles::validate_name(name).and_then(|_| {
+                profiles
+                    .profiles
+                    .insert(name.clone(), profile::Profile { contest });
+                profiles.save()
+            })
+        }
+        Some(("remove", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+            if let Err(error) = Profiles::validate_name(name) {
+                Err(error)
+            } else if name == Profiles::DEFAULT_PROFILE {
+                Err("Default profile cannot be removed".to_string())
+            } else if profiles.profiles.remove(name).is_none() {
+                Err(format!("Unknown profile {}", name))
+            } else {
+                if profiles.current.as_ref() == Some(name) {
+                    profiles.current = None;
+                }
+                for path in [Profiles::data_path(name), Profiles::cache_path(name, None)] {
+                    let _ = std::fs::remove_dir_all(shellexpand::tilde(&path).as_ref());
+                }
+                profiles.save()
+            }
+        }
+        Some(("use", args)) => {
+            let name = args.get_one::<String>("name").unwrap();
+            Profiles::validate_name(name).and_then(|_| match profiles.get(name) {
+                None => Err(format!("Unknown profile {}", name)),
+                Some(_) => {
+                    profiles.current = Some(name.clone());
+                    profiles.save()
+                }
+            })
+        }
+        _ => return exit_code::USAGE,
+    };
+
+    match result {
+        Ok(_) => exit_code::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            exit_code::CONFIG
+        }
+    }
 }
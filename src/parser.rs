@@ -3,7 +3,10 @@ use crate::satori::*;
 pub trait SatoriParser {
     fn find_username(&self, page: &str) -> Option<String>;
     fn find_joined_contests(&self, page: &str) -> Option<Vec<Contest>>;
+    fn find_archived_contests(&self, page: &str) -> Option<Vec<Contest>>;
     fn find_problems(&self, page: &str) -> Option<Vec<Problem>>;
     fn find_details(&self, page: &str) -> Option<ResultDetails>;
     fn find_results(&self, page: &str) -> Option<Vec<ShortResult>>;
+    /// `None` if the submit form has no language select and Satori detects the language itself
+    fn find_language_choice(&self, page: &str) -> Option<LanguageChoice>;
 }
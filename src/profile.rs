@@ -0,0 +1,136 @@
+use crate::config::Config;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Settings of a single account
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub contest: Option<String>,
+}
+
+/// Named accounts, each with its own token and cache
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profiles {
+    pub current: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    pub const DEFAULT_PROFILE: &str = "default";
+    const PROFILES_PATH: &str = "~/.config/satori-cli/profiles.toml";
+    const DATA_PATH: &str = "~/.local/share/satori-cli/profiles";
+    const LEGACY_DATA_PATH: &str = "~/.local/share/satori-cli";
+    const CACHE_PATH: &str = "~/.cache/satori-cli";
+
+    pub fn load() -> Result<Profiles, String> {
+        let path = Profiles::path();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                toml::from_str(&content).map_err(|error| format!("{}: {}", path.display(), error))
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Profiles::default()),
+            Err(error) => Err(format!("{}: {}", path.display(), error)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Profiles::path();
+        let content = toml::to_string(self).map_err(|error| error.to_string())?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|error| format!("{}: {}", parent.display(), error))?;
+        }
+        std::fs::write(&path, content).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Default profile always exists, even if it was never added
+    pub fn get(&self, name: &str) -> Option<Profile> {
+        match self.profiles.get(name) {
+            Some(profile) => Some(profile.clone()),
+            None if name == Profiles::DEFAULT_PROFILE => Some(Profile::default()),
+            None => None,
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.profiles.keys().cloned().collect::<Vec<String>>();
+        if !self.profiles.contains_key(Profiles::DEFAULT_PROFILE) {
+            names.insert(0, Profiles::DEFAULT_PROFILE.to_string());
+        }
+        names
+    }
+
+    pub fn current(&self) -> &str {
+        self.current.as_deref().unwrap_or(Profiles::DEFAULT_PROFILE)
+    }
+
+    /// Names end up in paths, so only letters, digits, `_` and `-` are allowed
+    pub fn validate_name(name: &str) -> Result<(), String> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        match valid {
+            true => Ok(()),
+            false => Err(format!(
+                "Invalid profile name {}, use only letters, digits, `_` and `-`",
+                name
+            )),
+        }
+    }
+
+    /// Data directory of a profile, `token_path` decides whether its tokens are kept there
+    pub fn data_path(name: &str) -> String {
+        format!("{}/{}", Profiles::DATA_PATH, name)
+    }
+
+    /// Token of a profile on a Satori instance,
+    /// default profile keeps its token where it was stored before profiles existed
+    pub fn token_path(name: &str, instance: Option<&str>) -> String {
+        let directory = match name {
+            Profiles::DEFAULT_PROFILE => Profiles::LEGACY_DATA_PATH.to_string(),
+            name => Profiles::data_path(name),
+        };
+        match instance {
+            None => format!("{}/token.txt", directory),
+            Some(instance) => format!("{}/token@{}.txt", directory, instance),
+        }
+    }
+
+    /// Caches of other instances live inside the profile cache, so removing it removes them all
+    pub fn cache_path(name: &str, instance: Option<&str>) -> String {
+        match instance {
+            None => format!("{}/{}", Profiles::CACHE_PATH, name),
+            Some(instance) => format!("{}/{}/instances/{}", Profiles::CACHE_PATH, name, instance),
+        }
+    }
+
+    /// Host and port of a Satori instance, `None` for the default one
+    pub fn instance(url: &str) -> Option<String> {
+        let url = reqwest::Url::parse(url).ok()?;
+        let default = reqwest::Url::parse(Config::DEFAULT_URL).ok()?;
+        if url.host_str() == default.host_str()
+            && url.port_or_known_default() == default.port_or_known_default()
+        {
+            return None;
+        }
+        let instance = match url.port() {
+            Some(port) => format!("{}_{}", url.host_str()?, port),
+            None => url.host_str()?.to_string(),
+        };
+        Some(instance.replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '-',
+            "_",
+        ))
+    }
+
+    fn path() -> PathBuf {
+        PathBuf::from(shellexpand::tilde(Profiles::PROFILES_PATH).to_string())
+    }
+}
@@ -1,4 +1,5 @@
 pub trait Prompt {
     fn ask_for_credentials(&self) -> Option<(String, String)>;
-    fn choose_option(&self, message: &str, options: &Vec<String>) -> Option<usize>;
+    fn ask_for_password(&self, login: &str) -> Option<String>;
+    fn choose_option(&self, message: &str, options: &[String]) -> Option<usize>;
 }
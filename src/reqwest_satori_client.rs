@@ -1,20 +1,48 @@
-use crate::satori_client::SatoriClient;
+use crate::satori_client::{ClientError, ClientResult, SatoriClient};
 
+use cookie_store::CookieExpiration;
+use rand::Rng;
 use reqwest_cookie_store::CookieStoreMutex;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    pub connect_timeout: Duration,
+    pub timeout: Duration,
+    /// How many times a failed GET is repeated, POSTs are never repeated
+    pub retries: u32,
+    /// Delay before the first retry, doubled with every next one up to 30 seconds
+    pub retry_delay: Duration,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(60),
+            retries: 3,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+}
 
 pub struct ReqwestSatoriClient {
     base_url: reqwest::Url,
     domain: String,
     token_name: String,
+    settings: ClientSettings,
     client: reqwest::blocking::Client,
     cookie_store: Arc<CookieStoreMutex>,
 }
 
 impl ReqwestSatoriClient {
-    pub fn new(base_url: &str, token_name: &str) -> Self {
+    pub fn new(base_url: &str, token_name: &str, settings: ClientSettings) -> Self {
         let base_url = reqwest::Url::parse(base_url).unwrap();
-        let domain = base_url.domain().unwrap().to_string();
+        // Host rather than domain, so a local test server on an IP address works too
+        let domain = base_url.host_str().unwrap().to_string();
 
         let cookie_store = reqwest_cookie_store::CookieStore::default();
         let cookie_store = reqwest_cookie_store::CookieStoreMutex::new(cookie_store);
@@ -22,6 +50,8 @@ impl ReqwestSatoriClient {
 
         let client = reqwest::blocking::Client::builder()
             .cookie_provider(std::sync::Arc::clone(&cookie_store))
+            .connect_timeout(settings.connect_timeout)
+            .timeout(settings.timeout)
             .build()
             .unwrap();
 
@@ -29,6 +59,7 @@ impl ReqwestSatoriClient {
             base_url,
             domain,
             token_name: token_name.to_string(),
+            settings,
             client,
             cookie_store,
         }
@@ -42,13 +73,24 @@ impl ReqwestSatoriClient {
             .map(|cookie| cookie.value().to_string())
     }
 
+    pub fn get_token_expiry(&self) -> Option<SystemTime> {
+        let store = self.cookie_store.lock().unwrap();
+        let cookie = store.get(&self.domain, "/", &self.token_name)?;
+        match cookie.expires {
+            CookieExpiration::AtUtc(expiry) => Some(expiry.into()),
+            CookieExpiration::SessionEnd => None,
+        }
+    }
+
     pub fn set_token(&self, token: &str) {
-        let cookie = cookie::Cookie::build(&self.token_name, token)
-            .domain(&self.domain)
+        let mut cookie = cookie::Cookie::build(&self.token_name, token)
             .path("/")
-            .secure(true)
+            .secure(self.base_url.scheme() == "https")
             .http_only(true)
             .finish();
+        if let Some(domain) = self.base_url.domain() {
+            cookie.set_domain(domain.to_string());
+        }
         self.cookie_store
             .lock()
             .unwrap()
@@ -56,41 +98,107 @@ impl ReqwestSatoriClient {
             .unwrap();
     }
 
-    pub fn log_in(&self, login: &str, password: &str) -> reqwest::Result<()> {
-        self.client
-            .post(self.get_url("login"))
-            .form(&[("login", login), ("password", password)])
-            .send()
-            .unwrap();
-        Ok(())
+    pub fn get_url(&self, path: &str) -> ClientResult<reqwest::Url> {
+        self.base_url
+            .join(path)
+            .map_err(|error| ClientError::InvalidUrl(error.to_string()))
     }
 
-    pub fn get_url(&self, path: &str) -> reqwest::Url {
-        self.base_url.join(path).unwrap()
+    pub fn do_get(&self, path: &str) -> ClientResult<reqwest::blocking::Response> {
+        let url = self.get_url(path)?;
+        let mut attempt = 0;
+        loop {
+            let response = check_response(self.client.get(url.clone()).send());
+            match response {
+                Err(error) if attempt < self.settings.retries && is_transient(&error) => {
+                    std::thread::sleep(self.retry_delay(attempt));
+                    attempt += 1;
+                }
+                response => return response,
+            }
+        }
     }
 
-    pub fn do_get(&self, path: &str) -> reqwest::blocking::Response {
-        self.client.get(self.get_url(path)).send().unwrap()
+    /// Exponential backoff with up to 50% of random jitter, never longer than MAX_RETRY_DELAY
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .settings
+            .retry_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_DELAY);
+        delay
+            .mul_f64(1.0 + rand::thread_rng().gen_range(0.0..0.5))
+            .min(MAX_RETRY_DELAY)
     }
 
-    pub fn do_post(&self, path: &str, data: &[(&str, &str)]) -> reqwest::blocking::Response {
-        self.client
-            .post(self.get_url(path))
-            .form(data)
-            .send()
-            .unwrap()
+    pub fn do_post(
+        &self,
+        path: &str,
+        data: &[(&str, &str)],
+    ) -> ClientResult<reqwest::blocking::Response> {
+        let response = self.client.post(self.get_url(path)?).form(data).send();
+        check_response(response)
     }
 
     pub fn do_multipart_post(
         &self,
         path: &str,
         form: reqwest::blocking::multipart::Form,
-    ) -> reqwest::blocking::Response {
-        self.client
-            .post(self.get_url(path))
-            .multipart(form)
-            .send()
-            .unwrap()
+    ) -> ClientResult<reqwest::blocking::Response> {
+        let response = self.client.post(self.get_url(path)?).multipart(form).send();
+        check_response(response)
+    }
+}
+
+fn check_response(
+    response: reqwest::Result<reqwest::blocking::Response>,
+) -> ClientResult<reqwest::blocking::Response> {
+    let response = response.map_err(classify_error)?;
+    match response.status().is_success() {
+        true => Ok(response),
+        false => Err(ClientError::HttpStatus(response.status().as_u16())),
+    }
+}
+
+fn is_transient(error: &ClientError) -> bool {
+    match error {
+        ClientError::Timeout | ClientError::Connect(_) | ClientError::Io(_) => true,
+        ClientError::HttpStatus(status) => *status == 429 || *status >= 500,
+        _ => false,
+    }
+}
+
+fn classify_error(error: reqwest::Error) -> ClientError {
+    if error.is_timeout() {
+        return ClientError::Timeout;
+    }
+    if let Some(status) = error.status() {
+        return ClientError::HttpStatus(status.as_u16());
+    }
+
+    // reqwest does not expose TLS and I/O failures directly, look for them in the chain of causes
+    let mut causes = Vec::new();
+    let mut source = std::error::Error::source(&error);
+    while let Some(cause) = source {
+        causes.push(cause);
+        source = cause.source();
+    }
+    let message = causes
+        .last()
+        .map_or_else(|| error.to_string(), |cause| cause.to_string());
+
+    let lowercase = message.to_lowercase();
+    if ["tls", "ssl", "certificate", "handshake"]
+        .iter()
+        .any(|keyword| lowercase.contains(keyword))
+    {
+        ClientError::Tls(message)
+    } else if error.is_connect() {
+        ClientError::Connect(message)
+    } else if causes.iter().any(|cause| cause.is::<std::io::Error>()) {
+        ClientError::Io(message)
+    } else {
+        ClientError::Other(message)
     }
 }
 
@@ -103,37 +211,40 @@ impl SatoriClient for ReqwestSatoriClient {
         self.set_token(token)
     }
 
-    fn get_url(&self, path: &str) -> String {
-        self.get_url(path).to_string()
+    fn get_token_expiry(&self) -> Option<SystemTime> {
+        self.get_token_expiry()
     }
 
-    fn get(&self, path: &str) -> Option<String> {
-        let response = self.do_get(path);
-        if response.status().is_success() {
-            Some(response.text().unwrap())
-        } else {
-            None
-        }
+    fn get(&self, path: &str) -> ClientResult<String> {
+        self.do_get(path)?.text().map_err(classify_error)
     }
 
-    fn post(&self, path: &str, data: &[(&str, &str)]) -> Option<String> {
-        let response = self.do_post(path, data);
-        if response.status().is_success() {
-            Some(response.text().unwrap())
-        } else {
-            None
-        }
+    fn get_bytes(&self, path: &str) -> ClientResult<Vec<u8>> {
+        let bytes = self.do_get(path)?.bytes().map_err(classify_error)?;
+        Ok(bytes.to_vec())
+    }
+
+    fn post(&self, path: &str, data: &[(&str, &str)]) -> ClientResult<String> {
+        self.do_post(path, data)?.text().map_err(classify_error)
     }
 
-    fn submit_file(&self, path: &str, file_name: &str, file_path: &str) -> Option<String> {
-        let form = reqwest::blocking::multipart::Form::new()
+    fn submit_file(
+        &self,
+        path: &str,
+        data: &[(&str, &str)],
+        file_name: &str,
+        file_path: &str,
+    ) -> ClientResult<String> {
+        let form = data
+            .iter()
+            .fold(
+                reqwest::blocking::multipart::Form::new(),
+                |form, (key, value)| form.text(key.to_string(), value.to_string()),
+            )
             .file(file_name.to_string(), file_path)
-            .unwrap();
-        let response = self.do_multipart_post(path, form);
-        if response.status().is_success() {
-            Some(response.text().unwrap())
-        } else {
-            None
-        }
+            .map_err(|error| ClientError::Io(error.to_string()))?;
+        self.do_multipart_post(path, form)?
+            .text()
+            .map_err(classify_error)
     }
 }
@@ -1,11 +1,25 @@
-#[derive(Debug)]
+use crate::satori_client::ClientError;
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContestStatus {
+    Active,
+    Archived,
+    Pending,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Contest {
     pub id: String,
     pub name: String,
     pub description: String,
+    pub status: ContestStatus,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Problem {
     pub contest_id: String,
     pub id: String,
@@ -16,45 +30,199 @@ pub struct Problem {
     pub submit_url: String,
 }
 
-#[derive(Debug)]
+/// Verdict of a submission or a test case, serialized as the code Satori shows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Verdict {
+    Ok,
+    Ans,
+    Tle,
+    Rte,
+    Mem,
+    Cme,
+    Que,
+    Int,
+    Ext,
+    Unknown(String),
+}
+
+impl Verdict {
+    pub fn code(&self) -> &str {
+        match self {
+            Verdict::Ok => "OK",
+            Verdict::Ans => "ANS",
+            Verdict::Tle => "TLE",
+            Verdict::Rte => "RTE",
+            Verdict::Mem => "MEM",
+            Verdict::Cme => "CME",
+            Verdict::Que => "QUE",
+            Verdict::Int => "INT",
+            Verdict::Ext => "EXT",
+            Verdict::Unknown(code) => code,
+        }
+    }
+
+    /// Queued submissions are still waiting for a verdict, an unknown one may be transient too
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Verdict::Que | Verdict::Unknown(_))
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        *self == Verdict::Ok
+    }
+
+    /// Judged and rejected because of the solution itself
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            Verdict::Ans | Verdict::Tle | Verdict::Rte | Verdict::Mem | Verdict::Cme | Verdict::Ext
+        )
+    }
+
+    /// Not judged because of a problem on the side of Satori, or a verdict this tool does not know
+    pub fn is_error(&self) -> bool {
+        matches!(self, Verdict::Int | Verdict::Unknown(_))
+    }
+
+    /// Higher is better: accepted, then by how far the solution got, then errors and the queue
+    pub(crate) fn rank(&self) -> u8 {
+        match self {
+            Verdict::Ok => 6,
+            Verdict::Ans => 5,
+            Verdict::Tle | Verdict::Mem => 4,
+            Verdict::Rte => 3,
+            Verdict::Cme | Verdict::Ext => 2,
+            Verdict::Int | Verdict::Unknown(_) => 1,
+            Verdict::Que => 0,
+        }
+    }
+}
+
+impl From<&str> for Verdict {
+    fn from(code: &str) -> Verdict {
+        match code {
+            "OK" => Verdict::Ok,
+            "ANS" => Verdict::Ans,
+            "TLE" => Verdict::Tle,
+            "RTE" => Verdict::Rte,
+            "MEM" => Verdict::Mem,
+            "CME" => Verdict::Cme,
+            "QUE" => Verdict::Que,
+            "INT" => Verdict::Int,
+            "EXT" => Verdict::Ext,
+            code => Verdict::Unknown(code.to_string()),
+        }
+    }
+}
+
+impl From<String> for Verdict {
+    fn from(code: String) -> Verdict {
+        Verdict::from(code.as_str())
+    }
+}
+
+impl From<Verdict> for String {
+    fn from(verdict: Verdict) -> String {
+        verdict.code().to_string()
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ShortResult {
     pub submission_id: String,
     pub problem_code: String,
     pub time: String,
-    pub status: String,
+    pub status: Verdict,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct TestCaseResult {
     pub test_case: String,
-    pub status: String,
+    pub status: Verdict,
     pub time: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ResultDetails {
     pub submission_id: String,
     pub problem_code: String,
     pub time: String,
-    pub status: String,
+    pub status: Verdict,
     pub test_results: Vec<TestCaseResult>,
 }
 
-#[derive(Debug)]
+/// Language select of a submit form, `field` is the name of the form field
+#[derive(Debug, PartialEq)]
+pub struct LanguageChoice {
+    pub field: String,
+    pub options: Vec<LanguageOption>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LanguageOption {
+    pub value: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub username: String,
+    /// Seconds until the token expires, `None` if it lasts until the browser session ends
+    pub valid_for: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum StatusMode {
+    Best,
+    Recent,
+}
+
+/// New submission with the contest it was resolved to, so it can be watched without asking again
+#[derive(Debug, Serialize)]
+pub struct Submission {
+    pub contest_id: String,
+    pub submission_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProblemStatus {
+    pub problem_code: String,
+    pub result: Option<ShortResult>,
+    pub attempts: usize,
+    pub first_ok_time: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "error", content = "details", rename_all = "snake_case")]
 pub enum SatoriError {
     NotLoggedIn,
     LoginFailed,
+    /// Gave up after this many failed logins
+    LoginAttemptsExceeded(u32),
+    /// Logging in succeeded, but Satori still returns a logged-out page
+    SessionRejected,
     ParsingFailed,
-    ConnectionFailed,
+    ConnectionFailed(ClientError),
     InvalidChoice,
     AmbiguousContest(AmbiguousNameError<Contest>),
     AmbiguousProblem(AmbiguousNameError<Problem>),
     ContestNotFound,
     ProblemNotFound,
     SubmissionNotFound,
+    SubmissionClosed,
+    FileError(String),
+    UnsupportedLanguage(String),
+    /// Submission was still not judged after this many seconds
+    WatchTimedOut(u64),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct AmbiguousNameError<T> {
     pub name: String,
     pub candidates: Vec<T>,
@@ -66,10 +234,12 @@ pub trait Satori {
     fn username(&self) -> SatoriResult<String>;
     fn contests(&self, archived: bool, force: bool) -> SatoriResult<Vec<Contest>>;
     fn details(&self, contest: &str, submission: &str, force: bool) -> SatoriResult<ResultDetails>;
-    fn login(&self, login: &str, password: &str) -> SatoriResult<String>;
+    /// Raw page, logged in or not, with the token redacted
+    fn dump_page(&self, path: &str) -> SatoriResult<String>;
+    fn login(&self, login: &str, password: &str) -> SatoriResult<Session>;
     fn logout(&self) -> SatoriResult<()>;
     fn problems(&self, contest: &str, force: bool) -> SatoriResult<Vec<Problem>>;
-    fn pdf(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<()>;
+    fn pdf(&self, contest: &str, problem: &str, output: &str, force: bool) -> SatoriResult<String>;
     fn results(
         &self,
         contest: &str,
@@ -77,6 +247,77 @@ pub trait Satori {
         limit: Option<usize>,
         force: bool,
     ) -> SatoriResult<Vec<ShortResult>>;
-    fn status(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<String>;
-    fn submit(&self, contest: &str, problem: &str, file_path: &str) -> SatoriResult<()>;
+    fn status(
+        &self,
+        contest: &str,
+        problem: &str,
+        mode: StatusMode,
+        force: bool,
+    ) -> SatoriResult<ProblemStatus>;
+    fn submit(
+        &self,
+        contest: &str,
+        problem: &str,
+        file_path: &str,
+        language: Option<&str>,
+    ) -> SatoriResult<Submission>;
+    fn watch(
+        &self,
+        contest: &str,
+        submission: &str,
+        timeout: Duration,
+    ) -> SatoriResult<ResultDetails>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdict_keeps_unknown_codes() {
+        assert_eq!(Verdict::from("MEM"), Verdict::Mem);
+        assert_eq!(Verdict::from("XYZ"), Verdict::Unknown("XYZ".to_string()));
+        assert_eq!(Verdict::from("XYZ").code(), "XYZ");
+    }
+
+    #[test]
+    fn verdict_is_serialized_as_code() {
+        assert_eq!(serde_json::to_string(&Verdict::Tle).unwrap(), "\"TLE\"");
+        assert_eq!(
+            serde_json::from_str::<Verdict>("\"QUE\"").unwrap(),
+            Verdict::Que
+        );
+    }
+
+    #[test]
+    fn better_verdicts_rank_higher() {
+        let mut verdicts = vec![
+            Verdict::Ans,
+            Verdict::Que,
+            Verdict::Ok,
+            Verdict::Int,
+            Verdict::Cme,
+            Verdict::Tle,
+        ];
+        verdicts.sort_by_key(Verdict::rank);
+        assert_eq!(
+            verdicts,
+            vec![
+                Verdict::Que,
+                Verdict::Int,
+                Verdict::Cme,
+                Verdict::Tle,
+                Verdict::Ans,
+                Verdict::Ok,
+            ]
+        );
+    }
+
+    #[test]
+    fn queued_and_unknown_verdicts_are_not_final() {
+        assert!(!Verdict::Que.is_final());
+        assert!(!Verdict::Unknown("XYZ".to_string()).is_final());
+        assert!(Verdict::Int.is_final());
+        assert_eq!(Verdict::Tle.rank(), Verdict::Mem.rank());
+    }
 }
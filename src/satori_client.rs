@@ -1,8 +1,49 @@
+use serde::Serialize;
+
+use std::fmt;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum ClientError {
+    Timeout,
+    Tls(String),
+    Connect(String),
+    HttpStatus(u16),
+    Io(String),
+    InvalidUrl(String),
+    Other(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Timeout => write!(f, "request timed out"),
+            ClientError::Tls(message) => write!(f, "TLS error: {}", message),
+            ClientError::Connect(message) => write!(f, "cannot connect: {}", message),
+            ClientError::HttpStatus(status) => write!(f, "server responded with HTTP {}", status),
+            ClientError::Io(message) => write!(f, "I/O error: {}", message),
+            ClientError::InvalidUrl(message) => write!(f, "invalid URL: {}", message),
+            ClientError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
 pub trait SatoriClient {
     fn get_token(&self) -> Option<String>;
     fn set_token(&self, token: &str);
-    fn get_url(&self, path: &str) -> String;
-    fn get(&self, path: &str) -> Option<String>;
-    fn post(&self, path: &str, data: &[(&str, &str)]) -> Option<String>;
-    fn submit_file(&self, path: &str, file_name: &str, file_path: &str) -> Option<String>;
+    /// `None` if there is no token or it expires with the session
+    fn get_token_expiry(&self) -> Option<SystemTime>;
+    fn get(&self, path: &str) -> ClientResult<String>;
+    fn get_bytes(&self, path: &str) -> ClientResult<Vec<u8>>;
+    fn post(&self, path: &str, data: &[(&str, &str)]) -> ClientResult<String>;
+    fn submit_file(
+        &self,
+        path: &str,
+        data: &[(&str, &str)],
+        file_name: &str,
+        file_path: &str,
+    ) -> ClientResult<String>;
 }
@@ -1,12 +1,20 @@
 use crate::display::*;
 use crate::satori::*;
+use crate::test_runner::LocalTestReport;
 
 use console::style;
+use indicatif::ProgressBar;
+use std::cell::RefCell;
+use std::time::Duration;
 
-pub struct SimpleDisplay {}
+pub struct SimpleDisplay {
+    spinner: RefCell<Option<ProgressBar>>,
+}
 impl SimpleDisplay {
     pub fn new() -> SimpleDisplay {
-        SimpleDisplay {}
+        SimpleDisplay {
+            spinner: RefCell::new(None),
+        }
     }
     fn print_error(&self, error: &SatoriError) {
         match error {
@@ -16,11 +24,17 @@ impl SimpleDisplay {
             SatoriError::LoginFailed => {
                 println!("Login failed.");
             }
+            SatoriError::LoginAttemptsExceeded(attempts) => {
+                println!("Login failed {} times, giving up.", attempts);
+            }
+            SatoriError::SessionRejected => {
+                println!("Logged in, but Satori still returns a logged-out page. The page layout may have changed.");
+            }
             SatoriError::ParsingFailed => {
                 println!("Parsing failed.");
             }
-            SatoriError::ConnectionFailed => {
-                println!("Connection failed.");
+            SatoriError::ConnectionFailed(cause) => {
+                println!("Connection failed: {}.", cause);
             }
             SatoriError::AmbiguousContest(ambiguous_name_error) => {
                 println!(
@@ -49,25 +63,46 @@ impl SimpleDisplay {
             SatoriError::SubmissionNotFound => {
                 println!("Submission not found.");
             }
+            SatoriError::SubmissionClosed => {
+                println!("Submitting to this problem is closed.");
+            }
+            SatoriError::FileError(message) => {
+                println!("File error: {}.", message);
+            }
+            SatoriError::UnsupportedLanguage(language) => {
+                println!("Unsupported language: {}.", language);
+            }
+            SatoriError::WatchTimedOut(seconds) => {
+                println!("Still not judged after {} seconds, giving up.", seconds);
+            }
 
             SatoriError::InvalidChoice => {
                 println!("Invalid choice.");
             }
+        }
+    }
 
-            _ => {
-                println!("Error: {:?}", error);
-            }
+    fn format_duration(seconds: u64) -> String {
+        let days = seconds / (24 * 60 * 60);
+        let hours = seconds / (60 * 60) % 24;
+        let minutes = seconds / 60 % 60;
+        match (days, hours) {
+            (0, 0) => format!("{} minutes", minutes),
+            (0, _) => format!("{} hours {} minutes", hours, minutes),
+            _ => format!("{} days {} hours", days, hours),
         }
     }
 
-    fn style_status(status: &str) -> console::StyledObject<&str> {
-        match status {
-            "OK" => style(status).green(),
-            "ANS" => style(status).red(),
-            "TLE" => style(status).red(),
-            "RTE" => style(status).red(),
-            "QUE" => style(status).yellow(),
-            _ => style(status).red(),
+    fn style_status(status: &Verdict) -> console::StyledObject<&str> {
+        let code = style(status.code());
+        if status.is_accepted() {
+            code.green()
+        } else if status.is_rejected() {
+            code.red()
+        } else if !status.is_final() {
+            code.yellow()
+        } else {
+            code.magenta()
         }
     }
 }
@@ -91,12 +126,19 @@ impl SatoriDisplay for SimpleDisplay {
     fn display_contests(&self, contests: &SatoriResult<Vec<Contest>>) {
         handle_error!(self, contests);
         for contest in contests {
-            print!("[{}] {}", contest.id, style(&contest.name).bold());
+            match contest.status {
+                ContestStatus::Pending => print!("{}", style(&contest.name).bold()),
+                _ => print!("[{}] {}", contest.id, style(&contest.name).bold()),
+            }
 
-            if contest.description != "" {
-                println!(" ({})", contest.description);
-            } else {
-                println!();
+            if !contest.description.is_empty() {
+                print!(" ({})", contest.description);
+            }
+
+            match contest.status {
+                ContestStatus::Active => println!(),
+                ContestStatus::Archived => println!(" {}", style("archived").dim()),
+                ContestStatus::Pending => println!(" {}", style("pending").yellow()),
             }
         }
     }
@@ -120,7 +162,7 @@ impl SatoriDisplay for SimpleDisplay {
         let status_len = details
             .test_results
             .iter()
-            .map(|r| r.status.len())
+            .map(|r| r.status.code().len())
             .max()
             .unwrap_or(0);
 
@@ -133,38 +175,40 @@ impl SatoriDisplay for SimpleDisplay {
                 width = status_len
             );
 
-            println!(
-                "{} {} {}",
-                test_case,
-                Self::style_status(&result.status),
-                result.time
-            );
+            println!("{} {} {}", test_case, status, result.time);
         }
     }
 
-    fn display_login(&self, login: &SatoriResult<String>) {
+    fn display_login(&self, login: &SatoriResult<Session>) {
         handle_error!(self, login);
-        println!("Logged in as {}.", style(login).bold());
+        println!("Logged in as {}.", style(&login.username).bold());
+        match login.valid_for {
+            Some(seconds) => println!("Session is valid for {}.", Self::format_duration(seconds)),
+            None => println!("Session is valid until the token is cleared."),
+        }
     }
 
     fn display_logout(&self, logout: &SatoriResult<()>) {
-        handle_error!(self, logout);
+        if let Err(error) = logout {
+            self.print_error(error);
+            return;
+        }
         println!("Logged out.");
     }
 
     fn display_problems(&self, problems: &SatoriResult<Vec<Problem>>) {
         handle_error!(self, problems);
         for problem in problems {
-            if problem.id != "" {
+            if !problem.id.is_empty() {
                 print!("[{}] ", problem.id);
             }
             println!("{} {}", style(&problem.code).bold(), problem.name);
         }
     }
 
-    fn display_pdf(&self, pdf: &SatoriResult<()>) {
+    fn display_pdf(&self, pdf: &SatoriResult<String>) {
         handle_error!(self, pdf);
-        println!("PDF: {:?}", pdf);
+        println!("Problem statement saved to {}.", style(pdf).bold());
     }
 
     fn display_results(&self, results: &SatoriResult<Vec<ShortResult>>) {
@@ -180,32 +224,102 @@ impl SatoriDisplay for SimpleDisplay {
             .map(|r| r.problem_code.len())
             .max()
             .unwrap_or(0);
-        let time_len = results.iter().map(|r| r.time.len()).max().unwrap_or(0);
-        let status_len = results.iter().map(|r| r.status.len()).max().unwrap_or(0);
+        let status_len = results
+            .iter()
+            .map(|r| r.status.code().len())
+            .max()
+            .unwrap_or(0);
 
         for result in results {
             // align columns
             let id = format!("{:width$}", result.submission_id, width = id_len);
             let code = format!("{:width$}", result.problem_code, width = code_len);
-            let time = format!("{:width$}", result.time, width = time_len);
             let status = format!(
                 "{:width$}",
                 Self::style_status(&result.status),
                 width = status_len
             );
 
-            println!("[{}] {} {}", id, style(&code).bold(), status,);
+            println!("[{}] {} {}", id, style(&code).bold(), status);
         }
     }
 
-    fn display_status(&self, status: &SatoriResult<String>) {
+    fn display_status(&self, status: &SatoriResult<ProblemStatus>) {
         handle_error!(self, status);
-        println!("Status: {:?}", status);
+        let result = match &status.result {
+            None => {
+                println!("{} not submitted yet.", style(&status.problem_code).bold());
+                return;
+            }
+            Some(result) => result,
+        };
+
+        println!(
+            "[{}] {} {} {}",
+            result.submission_id,
+            style(&status.problem_code).bold(),
+            result.time,
+            Self::style_status(&result.status)
+        );
+        print!("Attempts: {}", status.attempts);
+        if let Some(time) = &status.first_ok_time {
+            print!(", first OK at {}", time);
+        }
+        println!();
     }
 
-    fn display_submit(&self, submit: &SatoriResult<()>) {
+    fn display_submit(&self, submit: &SatoriResult<Submission>) {
         handle_error!(self, submit);
-        println!("Submit: {:?}", submit);
+        println!("Submitted as [{}].", style(&submit.submission_id).bold());
+    }
+
+    fn display_test_results(&self, report: &SatoriResult<LocalTestReport>) {
+        handle_error!(self, report);
+        println!(
+            "{} {}",
+            style(&report.problem_code).bold(),
+            Self::style_status(&report.status)
+        );
+
+        let test_case_len = report
+            .test_results
+            .iter()
+            .map(|r| r.result.test_case.len())
+            .max()
+            .unwrap_or(0);
+
+        for test in report.test_results.iter() {
+            // align columns, statuses of local tests are always three letters
+            let test_case = format!("{:>width$}", test.result.test_case, width = test_case_len);
+            let status = Self::style_status(&test.result.status);
+            match &test.difference {
+                Some(difference) => println!(
+                    "{} {} {} {}",
+                    test_case, status, test.result.time, difference
+                ),
+                None => println!("{} {} {}", test_case, status, test.result.time),
+            }
+        }
+    }
+
+    fn display_waiting(&self, submission: &str) {
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_message(format!(
+            "Waiting for submission [{}] to be judged",
+            submission
+        ));
+        spinner.enable_steady_tick(Duration::from_millis(100));
+        self.spinner.replace(Some(spinner));
+    }
+
+    fn display_waiting_done(&self) {
+        if let Some(spinner) = self.spinner.take() {
+            spinner.finish_and_clear();
+        }
+    }
+
+    fn display_watch(&self, details: &SatoriResult<ResultDetails>) {
+        self.display_details(details);
     }
 
     fn display_error(&self, error: &SatoriError) {
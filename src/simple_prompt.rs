@@ -0,0 +1,177 @@
+use crate::prompt::Prompt;
+
+use std::cell::Cell;
+use std::io::{BufRead, Write};
+
+/// Asks on the terminal, unless credentials are provided by other means.
+///
+/// Credentials are looked up in this order:
+/// - login from `SATORI_LOGIN` with password from `SATORI_PASSWORD` or stdin,
+/// - credential helper,
+/// - terminal, unless running non-interactively.
+///
+/// Provided credentials are used only once, so wrong ones are not retried forever.
+pub struct SimplePrompt {
+    interactive: bool,
+    password_stdin: bool,
+    credential_helper: Option<String>,
+    provided_used: Cell<bool>,
+}
+
+impl SimplePrompt {
+    const LOGIN_VARIABLE: &str = "SATORI_LOGIN";
+    const PASSWORD_VARIABLE: &str = "SATORI_PASSWORD";
+
+    pub fn new(
+        interactive: bool,
+        password_stdin: bool,
+        credential_helper: Option<String>,
+    ) -> SimplePrompt {
+        SimplePrompt {
+            interactive,
+            password_stdin,
+            credential_helper,
+            provided_used: Cell::new(false),
+        }
+    }
+
+    fn provided_credentials(&self) -> Option<(String, String)> {
+        if let Ok(login) = std::env::var(SimplePrompt::LOGIN_VARIABLE) {
+            if let Some(password) = self.provided_password() {
+                return Some((login, password));
+            }
+        } else if self.password_stdin {
+            eprintln!(
+                "--password-stdin requires login in {} or --user",
+                SimplePrompt::LOGIN_VARIABLE
+            );
+        }
+        self.helper_credentials()
+    }
+
+    fn provided_password(&self) -> Option<String> {
+        if let Ok(password) = std::env::var(SimplePrompt::PASSWORD_VARIABLE) {
+            return Some(password);
+        }
+        self.stdin_password()
+    }
+
+    /// `SATORI_PASSWORD` is not used if `SATORI_LOGIN` names another account
+    fn provided_password_for(&self, login: &str) -> Option<String> {
+        match std::env::var(SimplePrompt::LOGIN_VARIABLE) {
+            Ok(provided_login) if provided_login != login => self.stdin_password(),
+            _ => self.provided_password(),
+        }
+    }
+
+    fn stdin_password(&self) -> Option<String> {
+        if !self.password_stdin {
+            return None;
+        }
+        let mut password = String::new();
+        std::io::stdin().lock().read_line(&mut password).ok()?;
+        Some(password.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Runs `<helper> get` and reads `username=...` and `password=...` lines, like git does
+    fn helper_credentials(&self) -> Option<(String, String)> {
+        let helper = self.credential_helper.as_ref()?;
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} get", helper))
+            .stdin(std::process::Stdio::null())
+            .output();
+        let output = match output {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                eprintln!("Credential helper failed with {}", output.status);
+                return None;
+            }
+            Err(error) => {
+                eprintln!("Cannot run credential helper: {}", error);
+                return None;
+            }
+        };
+
+        let mut login = None;
+        let mut password = None;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            match line.split_once('=') {
+                Some(("username", value)) | Some(("login", value)) => {
+                    login = Some(value.to_string())
+                }
+                Some(("password", value)) => password = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Some((login?, password?))
+    }
+}
+
+impl Prompt for SimplePrompt {
+    fn ask_for_credentials(&self) -> Option<(String, String)> {
+        if !self.provided_used.replace(true) {
+            if let Some(credentials) = self.provided_credentials() {
+                return Some(credentials);
+            }
+        }
+        if !self.interactive {
+            return None;
+        }
+
+        let mut login = String::new();
+
+        eprint!("Login: ");
+        std::io::stderr().flush().unwrap();
+        std::io::stdin().read_line(&mut login).unwrap();
+        login.pop(); // remove newline
+        let password = rpassword::prompt_password("Password: ").unwrap();
+
+        Some((login, password))
+    }
+
+    fn ask_for_password(&self, login: &str) -> Option<String> {
+        if !self.provided_used.replace(true) {
+            if let Some(password) = self.provided_password_for(login) {
+                return Some(password);
+            }
+            match self.helper_credentials() {
+                Some((helper_login, password)) if helper_login == login => return Some(password),
+                _ => {}
+            }
+        }
+        if !self.interactive {
+            return None;
+        }
+
+        rpassword::prompt_password(format!("Password for {}: ", login)).ok()
+    }
+
+    fn choose_option(&self, message: &str, options: &[String]) -> Option<usize> {
+        if !self.interactive {
+            return None;
+        }
+
+        eprintln!("{}", message);
+        for (i, option) in options.iter().enumerate() {
+            eprintln!("{}. {}", i + 1, option);
+        }
+
+        let mut choice = String::new();
+        eprint!("Your choice: ");
+        std::io::stderr().flush().unwrap();
+        std::io::stdin().read_line(&mut choice).unwrap();
+        choice.pop(); // remove newline
+
+        match choice.parse::<usize>() {
+            Ok(choice) => {
+                if choice > 0 && choice <= options.len() {
+                    Some(choice - 1)
+                } else {
+                    None
+                }
+            }
+            Err(_) => None,
+        }
+    }
+}
@@ -1,9 +1,19 @@
+use crate::cache::{Cache, CacheKind};
+use crate::language::Language;
 use crate::parser::SatoriParser;
 use crate::satori::*;
 use crate::satori_client::SatoriClient;
 use crate::token_storage::TokenStorage;
-use futures::stream::ForEachConcurrent;
-use Satori;
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+const RESULTS_PAGE_SIZE: usize = 100;
+const ARCHIVED_CONTESTS_PATH: &str = "/contest/select?archived=1";
+const WATCH_INITIAL_DELAY: Duration = Duration::from_secs(2);
+const WATCH_MAX_DELAY: Duration = Duration::from_secs(30);
 
 enum UniqueSearchResult<T> {
     NotFound,
@@ -11,37 +21,67 @@ enum UniqueSearchResult<T> {
     Ambiguous(Vec<T>),
 }
 
-pub struct SimpleSatori<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> {
+pub struct SimpleSatori<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage, C: Cache> {
     client: Client,
     parser: Parser,
     token_storage: T,
+    cache: C,
 }
 
-impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<Client, Parser, T> {
-    pub fn new(client: Client, parser: Parser, token_storage: T) -> Self {
+impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage, C: Cache>
+    SimpleSatori<Client, Parser, T, C>
+{
+    pub fn new(client: Client, parser: Parser, token_storage: T, cache: C) -> Self {
         Self {
             client,
             parser,
             token_storage,
+            cache,
         }
     }
 
-    fn log_in(&self, login: &str, password: &str) -> Option<()> {
-        self.client
-            .post("/login", &[("login", login), ("password", password)])?;
-        self.token_storage.save_token(&self.client.get_token()?);
-        Some(())
+    fn load_cached<V: DeserializeOwned>(&self, kind: CacheKind, key: &str) -> Option<V> {
+        let value = self.cache.load(kind, key)?;
+        serde_json::from_str(&value).ok()
     }
 
-    fn get_and_ensure_logged_in(&self, path: &str) -> SatoriResult<String> {
-        // Try to use token first
+    fn store_cached<V: Serialize>(&self, kind: CacheKind, key: &str, value: &V) {
+        if let Ok(value) = serde_json::to_string(value) {
+            self.cache.store(kind, key, &value);
+        }
+    }
+
+    fn cached<V: Serialize + DeserializeOwned>(
+        &self,
+        kind: CacheKind,
+        key: &str,
+        force: bool,
+        fetch: impl FnOnce() -> SatoriResult<V>,
+    ) -> SatoriResult<V> {
+        if !force {
+            if let Some(value) = self.load_cached(kind, key) {
+                return Ok(value);
+            }
+        }
+        let value = fetch()?;
+        self.store_cached(kind, key, &value);
+        Ok(value)
+    }
+
+    /// Pages may come from the cache, so requests that follow them can't rely on the token being set
+    fn use_stored_token(&self) {
         if let Some(token) = self.token_storage.load_token() {
             self.client.set_token(&token);
         }
+    }
+
+    fn get_and_ensure_logged_in(&self, path: &str) -> SatoriResult<String> {
+        // Try to use token first
+        self.use_stored_token();
 
         match self.client.get(path) {
-            None => Err(SatoriError::ConnectionFailed),
-            Some(page) => {
+            Err(error) => Err(SatoriError::ConnectionFailed(error)),
+            Ok(page) => {
                 if self.parser.find_username(&page).is_some() {
                     Ok(page)
                 } else {
@@ -58,6 +98,7 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<C
     ) -> UniqueSearchResult<Contest> {
         let mut found_contests = contests
             .into_iter()
+            .filter(|contest| contest.status != ContestStatus::Pending)
             .filter(|contest| contest.id.starts_with(prefix) || contest.name.starts_with(prefix))
             .collect::<Vec<Contest>>();
 
@@ -68,6 +109,33 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<C
         }
     }
 
+    /// Exact match of value or name wins, otherwise the first option whose name starts with
+    /// `language`, so `C` selects `C (gcc)` but not `C++`
+    fn find_language_option<'a>(
+        &self,
+        choice: &'a LanguageChoice,
+        language: &str,
+    ) -> SatoriResult<&'a LanguageOption> {
+        let language = language.to_lowercase();
+        let exact = choice.options.iter().find(|option| {
+            option.value.to_lowercase() == language || option.name.to_lowercase() == language
+        });
+        let prefix = choice.options.iter().find(|option| {
+            let name = option.name.to_lowercase();
+            name.starts_with(&language) && !name[language.len()..].starts_with(['+', '#'])
+        });
+
+        exact.or(prefix).ok_or_else(|| {
+            let available = choice
+                .options
+                .iter()
+                .map(|option| option.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+            SatoriError::UnsupportedLanguage(format!("{} (available: {})", language, available))
+        })
+    }
+
     fn find_unique_problem(
         &self,
         problems: Vec<Problem>,
@@ -82,6 +150,17 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<C
             })
             .collect::<Vec<Problem>>();
 
+        // A code given in full wins over longer codes it is a prefix of, e.g. A over A2
+        if found_problems.len() > 1 {
+            let exact = found_problems
+                .iter()
+                .filter(|problem| problem.code == prefix)
+                .count();
+            if exact == 1 {
+                found_problems.retain(|problem| problem.code == prefix);
+            }
+        }
+
         match found_problems.len() {
             0 => UniqueSearchResult::NotFound,
             1 => UniqueSearchResult::Found(found_problems.pop().unwrap()),
@@ -89,9 +168,59 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<C
         }
     }
 
+    fn fetch_results(
+        &self,
+        contest_id: &str,
+        filters: &[String],
+        force: bool,
+    ) -> SatoriResult<Vec<ShortResult>> {
+        let query = match filters.is_empty() {
+            true => String::new(),
+            false => format!("?{}", filters.join("&")),
+        };
+        let path = format!("/contest/{}/results{}", contest_id, query);
+
+        self.cached(CacheKind::Results, &path, force, || {
+            let page = self.get_and_ensure_logged_in(&path)?;
+            match self.parser.find_results(&page) {
+                Some(results) => Ok(results),
+                None => Err(SatoriError::ParsingFailed),
+            }
+        })
+    }
+
+    fn joined_contests(&self, force: bool) -> SatoriResult<Vec<Contest>> {
+        self.cached(CacheKind::Contests, "joined", force, || {
+            let page = self.get_and_ensure_logged_in("/contest/select")?;
+            match self.parser.find_joined_contests(&page) {
+                Some(contests) => Ok(contests),
+                None => Err(SatoriError::ParsingFailed),
+            }
+        })
+    }
+
+    fn archived_contests(&self, force: bool) -> SatoriResult<Vec<Contest>> {
+        self.cached(CacheKind::Contests, "archived", force, || {
+            let page = self.get_and_ensure_logged_in(ARCHIVED_CONTESTS_PATH)?;
+            match self.parser.find_archived_contests(&page) {
+                Some(contests) => Ok(contests),
+                None => Err(SatoriError::ParsingFailed),
+            }
+        })
+    }
+
+    /// Resolves contest by prefix, refreshing cached contests if nothing matches.
+    /// Archived contests are searched only when no joined contest matches.
     fn contest(&self, contest: &str, force: bool) -> SatoriResult<Contest> {
-        let contests = self.contests(false, force)?;
-        let contest = match self.find_unique_contest(contests, contest) {
+        let mut found = self.find_unique_contest(self.joined_contests(force)?, contest);
+        if let UniqueSearchResult::NotFound = found {
+            found = self.find_unique_contest(self.archived_contests(force)?, contest);
+        }
+        if !force && matches!(found, UniqueSearchResult::NotFound) {
+            return self.contest(contest, true);
+        }
+
+        let contest = match found {
             UniqueSearchResult::NotFound => return Err(SatoriError::ContestNotFound),
             UniqueSearchResult::Ambiguous(contests) => {
                 return Err(SatoriError::AmbiguousContest(AmbiguousNameError {
@@ -101,12 +230,14 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<C
             }
             UniqueSearchResult::Found(contest) => contest,
         };
-        return Ok(contest);
+        Ok(contest)
     }
 
+    /// Resolves problem by prefix, refreshing cached problems if nothing matches
     fn problem(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<Problem> {
         let problems = self.problems(contest, force)?;
         let problem = match self.find_unique_problem(problems, problem) {
+            UniqueSearchResult::NotFound if !force => return self.problem(contest, problem, true),
             UniqueSearchResult::NotFound => return Err(SatoriError::ProblemNotFound),
             UniqueSearchResult::Ambiguous(problems) => {
                 return Err(SatoriError::AmbiguousProblem(AmbiguousNameError {
@@ -116,71 +247,142 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> SimpleSatori<C
             }
             UniqueSearchResult::Found(problem) => problem,
         };
-        return Ok(problem);
+        Ok(problem)
     }
 }
 
-impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> Satori
-    for SimpleSatori<Client, Parser, T>
+impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage, C: Cache> Satori
+    for SimpleSatori<Client, Parser, T, C>
 {
     fn username(&self) -> SatoriResult<String> {
         let page = self.get_and_ensure_logged_in("")?;
         Ok(self.parser.find_username(&page).unwrap())
     }
 
-    fn contests(&self, _archived: bool, _force: bool) -> Result<Vec<Contest>, SatoriError> {
-        let page = self.get_and_ensure_logged_in("/contest/select")?;
-
-        match self.parser.find_joined_contests(&page) {
-            Some(contests) => Ok(contests),
-            None => Err(SatoriError::ParsingFailed),
+    fn contests(&self, archived: bool, force: bool) -> Result<Vec<Contest>, SatoriError> {
+        let mut contests = self.joined_contests(force)?;
+        if archived {
+            contests.extend(self.archived_contests(force)?);
         }
+        Ok(contests)
     }
 
-    fn details(
-        &self,
-        contest: &str,
-        submission: &str,
-        _force: bool,
-    ) -> SatoriResult<ResultDetails> {
+    fn details(&self, contest: &str, submission: &str, force: bool) -> SatoriResult<ResultDetails> {
         let contest = self.contest(contest, false)?;
-        let page = self
-            .get_and_ensure_logged_in(&format!("/contest/{}/results/{}", contest.id, submission))?;
+        let path = format!("/contest/{}/results/{}", contest.id, submission);
+        if !force {
+            if let Some(details) = self.load_cached(CacheKind::Details, &path) {
+                return Ok(details);
+            }
+        }
+
+        let page = self.get_and_ensure_logged_in(&path)?;
+        let details = match self.parser.find_details(&page) {
+            Some(details) => details,
+            None => return Err(SatoriError::ParsingFailed),
+        };
 
-        match self.parser.find_details(&page) {
-            Some(details) => Ok(details),
-            None => Err(SatoriError::ParsingFailed),
+        // Judged submissions never change, so only those are worth keeping
+        if details.status.is_final() {
+            self.store_cached(CacheKind::Details, &path, &details);
         }
+        Ok(details)
     }
 
-    fn login(&self, username: &str, password: &str) -> SatoriResult<String> {
-        match self.log_in(username, password) {
-            Some(_) => {
-                let page = self.get_and_ensure_logged_in("")?;
-                return Ok(self.parser.find_username(&page).unwrap());
-            }
-            None => Err(SatoriError::LoginFailed),
+    fn dump_page(&self, path: &str) -> SatoriResult<String> {
+        self.use_stored_token();
+        let page = self
+            .client
+            .get(path)
+            .map_err(SatoriError::ConnectionFailed)?;
+        match self.client.get_token() {
+            Some(token) if !token.is_empty() => Ok(page.replace(&token, "REDACTED")),
+            _ => Ok(page),
         }
     }
 
+    fn login(&self, login: &str, password: &str) -> SatoriResult<Session> {
+        // A rejected login leaves the expired token in place, so only a new token means success
+        let previous_token = self.client.get_token();
+        self.client
+            .post("/login", &[("login", login), ("password", password)])
+            .map_err(SatoriError::ConnectionFailed)?;
+        let token = self
+            .client
+            .get_token()
+            .filter(|token| Some(token) != previous_token.as_ref())
+            .ok_or(SatoriError::LoginFailed)?;
+
+        // Confirm the new session works before storing it, the token proves the credentials were fine
+        let page = self.client.get("").map_err(SatoriError::ConnectionFailed)?;
+        let username = self
+            .parser
+            .find_username(&page)
+            .ok_or(SatoriError::SessionRejected)?;
+        self.token_storage
+            .save_token(&token)
+            .map_err(|error| SatoriError::FileError(error.to_string()))?;
+
+        let valid_for = self.client.get_token_expiry().map(|expiry| {
+            expiry
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+                .as_secs()
+        });
+        Ok(Session {
+            username,
+            valid_for,
+        })
+    }
+
     fn logout(&self) -> SatoriResult<()> {
-        self.token_storage.clear_token();
-        Ok(())
+        self.cache.clear();
+        self.token_storage
+            .clear_token()
+            .map_err(|error| SatoriError::FileError(error.to_string()))
     }
 
     fn problems(&self, contest: &str, force: bool) -> SatoriResult<Vec<Problem>> {
-        let contest_id = &self.contest(contest, force)?.id;
-        println!("contest_id: {}", contest_id);
+        let contest_id = &self.contest(contest, false)?.id;
 
-        let page = self.get_and_ensure_logged_in(&format!("/contest/{}/problems", contest_id))?;
-        match self.parser.find_problems(&page) {
-            Some(problems) => Ok(problems),
-            None => Err(SatoriError::ParsingFailed),
-        }
+        self.cached(CacheKind::Problems, contest_id, force, || {
+            let page =
+                self.get_and_ensure_logged_in(&format!("/contest/{}/problems", contest_id))?;
+            match self.parser.find_problems(&page) {
+                Some(problems) => Ok(problems),
+                None => Err(SatoriError::ParsingFailed),
+            }
+        })
     }
 
-    fn pdf(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<()> {
-        todo!()
+    fn pdf(&self, contest: &str, problem: &str, output: &str, force: bool) -> SatoriResult<String> {
+        let contest = self.contest(contest, false)?;
+        let problem = self.problem(&contest.id, problem, false)?;
+
+        let directory = Path::new(output).join(contest.name.replace(['/', '\\'], "_"));
+        let path = directory.join(format!("{}.pdf", problem.code));
+        if path.exists() && !force {
+            return Ok(path.display().to_string());
+        }
+
+        self.use_stored_token();
+        let pdf = self
+            .client
+            .get_bytes(&problem.pdf_url)
+            .map_err(SatoriError::ConnectionFailed)?;
+
+        // Satori serves the login page instead of the statement when the token is invalid
+        if !pdf.starts_with(b"%PDF") {
+            return match self.parser.find_username(&String::from_utf8_lossy(&pdf)) {
+                None => Err(SatoriError::NotLoggedIn),
+                Some(_) => Err(SatoriError::ParsingFailed),
+            };
+        }
+
+        std::fs::create_dir_all(&directory)
+            .and_then(|_| std::fs::write(&path, pdf))
+            .map_err(|error| SatoriError::FileError(error.to_string()))?;
+        Ok(path.display().to_string())
     }
 
     fn results(
@@ -190,41 +392,520 @@ impl<Client: SatoriClient, Parser: SatoriParser, T: TokenStorage> Satori
         limit: Option<usize>,
         force: bool,
     ) -> SatoriResult<Vec<ShortResult>> {
-        let contest = self.contest(contest, force)?;
-        let limit_filter = match limit {
-            Some(limit) => format!("results_limit={}", limit),
-            None => "".to_string(),
-        };
+        let contest = self.contest(contest, false)?;
+        let mut filters = Vec::new();
+        if let Some(limit) = limit {
+            filters.push(format!("results_limit={}", limit));
+        }
+        let mut closed_problem = None;
+        if let Some(problem) = problem {
+            let problem = self.problem(contest.id.as_str(), problem, false)?;
+            match problem.id.is_empty() {
+                true => closed_problem = Some(problem.code),
+                false => filters.push(format!("results_filter_problem={}", problem.id)),
+            }
+        }
+
+        let results = self.fetch_results(&contest.id, &filters, force)?;
+        // A closed problem has no id to filter by, so its results are picked out by code
+        match closed_problem {
+            Some(code) => Ok(results
+                .into_iter()
+                .filter(|result| result.problem_code == code)
+                .collect()),
+            None => Ok(results),
+        }
+    }
 
-        let problem_filter = match problem {
-            Some(problem) => {
-                let problem = self.problem(contest.id.as_str(), problem, force)?;
-                format!("results_filter_problem={}", problem.id)
+    fn status(
+        &self,
+        contest: &str,
+        problem: &str,
+        mode: StatusMode,
+        force: bool,
+    ) -> SatoriResult<ProblemStatus> {
+        let contest = self.contest(contest, false)?;
+        let problem = self.problem(&contest.id, problem, false)?;
+
+        // Results are listed newest first, page by page
+        let mut results: Vec<ShortResult> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        for page in 1.. {
+            let mut filters = vec![
+                format!("results_limit={}", RESULTS_PAGE_SIZE),
+                format!("results_page={}", page),
+            ];
+            // A closed problem has no id to filter by, so its results are picked out by code below
+            if !problem.id.is_empty() {
+                filters.push(format!("results_filter_problem={}", problem.id));
             }
-            None => "".to_string(),
-        };
+            let page_results = self.fetch_results(&contest.id, &filters, force)?;
 
-        let filter = match (limit_filter.is_empty(), problem_filter.is_empty()) {
-            (true, true) => "".to_string(),
-            (false, true) => format!("?{}", limit_filter),
-            (true, false) => format!("?{}", problem_filter),
-            (false, false) => format!("?{}&{}", limit_filter, problem_filter),
+            // Stop on an empty or short page, or when the server ignores paging and repeats itself
+            let page_len = page_results.len();
+            let exhausted = page_results
+                .first()
+                .is_none_or(|first| seen.contains(&first.submission_id));
+            if exhausted {
+                break;
+            }
+            seen.extend(
+                page_results
+                    .iter()
+                    .map(|result| result.submission_id.clone()),
+            );
+            results.extend(
+                page_results
+                    .into_iter()
+                    .filter(|result| result.problem_code == problem.code),
+            );
+            if page_len < RESULTS_PAGE_SIZE {
+                break;
+            }
+        }
+
+        let first_ok_time = results
+            .iter()
+            .rev()
+            .find(|result| result.status.is_accepted())
+            .map(|result| result.time.clone());
+        let attempts = results.len();
+        let result = match mode {
+            StatusMode::Recent => results.into_iter().next(),
+            // Newest of the best verdicts
+            StatusMode::Best => results
+                .into_iter()
+                .min_by_key(|result| std::cmp::Reverse(result.status.rank())),
         };
 
-        let page =
-            self.get_and_ensure_logged_in(&format!("/contest/{}/results{}", &contest.id, filter))?;
+        Ok(ProblemStatus {
+            problem_code: problem.code,
+            result,
+            attempts,
+            first_ok_time,
+        })
+    }
+
+    fn submit(
+        &self,
+        contest: &str,
+        problem: &str,
+        file_path: &str,
+        language: Option<&str>,
+    ) -> SatoriResult<Submission> {
+        if !Path::new(file_path).is_file() {
+            return Err(SatoriError::FileError(format!(
+                "{} is not a file",
+                file_path
+            )));
+        }
+
+        let contest = self.contest(contest, false)?;
+        let problem = self.problem(&contest.id, problem, false)?;
+        if problem.submit_url.is_empty() {
+            return Err(SatoriError::SubmissionClosed);
+        }
+
+        // Make sure the token is set before posting the form
+        let form = self.get_and_ensure_logged_in(&problem.submit_url)?;
+        let mut data = vec![("problem", problem.id.as_str())];
+        let language_choice = self.parser.find_language_choice(&form);
+        if let Some(choice) = &language_choice {
+            let language = language
+                .or(Language::from_path(Path::new(file_path)).map(|language| language.name()))
+                .ok_or_else(|| SatoriError::UnsupportedLanguage(file_path.to_string()))?;
+            let option = self.find_language_option(choice, language)?;
+            data.push((&choice.field, &option.value));
+        } else if let Some(language) = language {
+            return Err(SatoriError::UnsupportedLanguage(format!(
+                "{} (the submit form offers no choice of language)",
+                language
+            )));
+        }
+
+        // Satori may not list the new submission yet, so an id seen before is never returned
+        let filters = [format!("results_filter_problem={}", problem.id)];
+        let previous = self.fetch_results(&contest.id, &filters, true)?;
+
+        let page = self
+            .client
+            .submit_file(
+                &format!("/contest/{}/submit", contest.id),
+                &data,
+                "codefile",
+                file_path,
+            )
+            .map_err(SatoriError::ConnectionFailed)?;
+        self.cache.invalidate(CacheKind::Results);
+
+        if self.parser.find_username(&page).is_none() {
+            return Err(SatoriError::NotLoggedIn);
+        }
+
+        // Satori redirects to the results page, newest submission first
+        let results = self
+            .parser
+            .find_results(&page)
+            .ok_or(SatoriError::ParsingFailed)?;
+        results
+            .into_iter()
+            .filter(|result| {
+                previous
+                    .iter()
+                    .all(|seen| seen.submission_id != result.submission_id)
+            })
+            .find(|result| result.problem_code == problem.code)
+            .map(|result| Submission {
+                contest_id: contest.id,
+                submission_id: result.submission_id,
+            })
+            .ok_or(SatoriError::SubmissionNotFound)
+    }
+
+    fn watch(
+        &self,
+        contest: &str,
+        submission: &str,
+        timeout: Duration,
+    ) -> SatoriResult<ResultDetails> {
+        let contest = self.contest(contest, false)?;
+        let start = Instant::now();
+        let mut delay = WATCH_INITIAL_DELAY;
+        loop {
+            let details = self.details(&contest.id, submission, true)?;
+            if details.status.is_final() {
+                return Ok(details);
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(SatoriError::WatchTimedOut(timeout.as_secs()));
+            }
+            std::thread::sleep(std::cmp::min(delay, remaining));
+            delay = std::cmp::min(delay * 2, WATCH_MAX_DELAY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes::*;
+    use crate::satori_client::ClientError;
+    use crate::soup_parser::SoupParser;
+
+    type FakeSatori = SimpleSatori<FakeClient, SoupParser, MemoryTokenStorage, MemoryCache>;
+
+    fn logged_in(client: FakeClient) -> FakeSatori {
+        SimpleSatori::new(
+            client,
+            SoupParser::new(),
+            MemoryTokenStorage::with_token(TOKEN),
+            MemoryCache::default(),
+        )
+    }
+
+    fn logged_out(token: MemoryTokenStorage) -> FakeSatori {
+        SimpleSatori::new(
+            FakeClient::new(),
+            SoupParser::new(),
+            token,
+            MemoryCache::default(),
+        )
+    }
+
+    #[test]
+    fn contests_are_cached() {
+        let client = FakeClient::new();
+        let requests = client.requests.clone();
+        let satori = logged_in(client);
+
+        let contests = satori.contests(false, false).unwrap();
+        assert_eq!(contests.len(), 3);
+        satori.contests(false, false).unwrap();
+        assert_eq!(*requests.borrow(), vec!["/contest/select"]);
+    }
+
+    #[test]
+    fn archived_contest_is_resolved_from_cache() {
+        let client = FakeClient::new();
+        let requests = client.requests.clone();
+        let satori = logged_in(client);
+
+        assert_eq!(
+            satori.contest("Algorithms 2022", false).unwrap().id,
+            "4873421"
+        );
+        satori.contest("Algorithms 2022", false).unwrap();
+        assert_eq!(
+            *requests.borrow(),
+            vec!["/contest/select", "/contest/select?archived=1"]
+        );
+    }
+
+    #[test]
+    fn expired_token_means_not_logged_in() {
+        let satori = logged_out(MemoryTokenStorage::with_token("expired-token"));
+        assert!(matches!(
+            satori.contests(false, false),
+            Err(SatoriError::NotLoggedIn)
+        ));
+    }
+
+    #[test]
+    fn login_stores_token() {
+        let satori = logged_out(MemoryTokenStorage::default());
+
+        let session = satori.login(LOGIN, PASSWORD).unwrap();
+        assert_eq!(session.username, "John Smith");
+        assert_eq!(satori.token_storage.load_token(), Some(TOKEN.to_string()));
+        assert!(satori.contests(false, false).is_ok());
+    }
+
+    #[test]
+    fn login_with_wrong_password_fails_despite_expired_token() {
+        let satori = logged_out(MemoryTokenStorage::with_token("expired-token"));
+        satori.contests(false, false).unwrap_err();
+
+        assert!(matches!(
+            satori.login(LOGIN, "wrong"),
+            Err(SatoriError::LoginFailed)
+        ));
+        assert_eq!(
+            satori.token_storage.load_token(),
+            Some("expired-token".to_string())
+        );
+    }
+
+    #[test]
+    fn contest_is_found_by_name_prefix() {
+        let satori = logged_in(FakeClient::new());
+        let problems = satori.problems("Algo", false).unwrap();
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn archived_contest_is_found_when_no_joined_matches() {
+        let satori = logged_in(FakeClient::new());
+        let error = satori.problems("Algorithms 2022", false).unwrap_err();
+        // Archived contest has no problems page in the fake, so resolution got past the contest
+        assert!(matches!(
+            error,
+            SatoriError::ConnectionFailed(ClientError::HttpStatus(404))
+        ));
+    }
 
-        match self.parser.find_results(&page) {
-            Some(results) => Ok(results),
-            None => Err(SatoriError::ParsingFailed),
+    #[test]
+    fn ambiguous_contest_lists_candidates() {
+        let satori = logged_in(FakeClient::new());
+        match satori.problems("5", false) {
+            Err(SatoriError::AmbiguousContest(error)) => {
+                let names = error
+                    .candidates
+                    .iter()
+                    .map(|contest| contest.name.as_str())
+                    .collect::<Vec<&str>>();
+                assert_eq!(names, vec!["Algorithms 2023", "Graph Theory"]);
+            }
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn pending_contest_is_not_found() {
+        let satori = logged_in(FakeClient::new());
+        assert!(matches!(
+            satori.problems("Competitive", false),
+            Err(SatoriError::ContestNotFound)
+        ));
+    }
+
+    #[test]
+    fn ambiguous_problem_lists_candidates() {
+        let satori = logged_in(FakeClient::new());
+        match satori.results("Algo", Some("S"), None, false) {
+            Err(SatoriError::AmbiguousProblem(error)) => assert_eq!(error.candidates.len(), 2),
+            result => panic!("unexpected {:?}", result),
         }
     }
 
-    fn status(&self, contest: &str, problem: &str, force: bool) -> SatoriResult<String> {
-        todo!()
+    #[test]
+    fn results_are_filtered_by_problem_and_limited() {
+        let client = FakeClient::new().route(
+            "/contest/5241304/results?results_limit=5&results_filter_problem=5241320",
+            fixture!("results"),
+        );
+        let requests = client.requests.clone();
+        let satori = logged_in(client);
+
+        let results = satori.results("Algo", Some("A"), Some(5), false).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            requests.borrow().last().unwrap(),
+            "/contest/5241304/results?results_limit=5&results_filter_problem=5241320"
+        );
+    }
+
+    #[test]
+    fn unknown_problem_is_not_found() {
+        let satori = logged_in(FakeClient::new());
+        assert!(matches!(
+            satori.results("Algo", Some("Z"), None, false),
+            Err(SatoriError::ProblemNotFound)
+        ));
+    }
+
+    #[test]
+    fn connection_error_is_propagated() {
+        let satori = logged_in(FakeClient::new());
+        assert!(matches!(
+            satori.results("Algo", None, None, false),
+            Err(SatoriError::ConnectionFailed(ClientError::HttpStatus(404)))
+        ));
+    }
+
+    #[test]
+    fn judged_details_are_cached() {
+        let client = FakeClient::new();
+        let requests = client.requests.clone();
+        let satori = logged_in(client);
+
+        let details = satori.details("Algo", "8812001", false).unwrap();
+        assert_eq!(details.status, Verdict::Ans);
+        let count = requests.borrow().len();
+        satori.details("Algo", "8812001", false).unwrap();
+        assert_eq!(requests.borrow().len(), count);
+    }
+
+    #[test]
+    fn pdf_is_downloaded_with_cached_problems() {
+        let pdf_url = "/view/ProblemMapping/5241320/statement_files/_pdf/a.pdf";
+        let client = FakeClient::new().route(pdf_url, "%PDF-1.4 statement");
+        let requests = client.requests.clone();
+        let parser = SoupParser::new();
+        let cache = MemoryCache::default();
+        let contests = parser.find_joined_contests(fixture!("contest_select"));
+        let problems = parser.find_problems(fixture!("problems"));
+        cache.store(
+            CacheKind::Contests,
+            "joined",
+            &serde_json::to_string(&contests).unwrap(),
+        );
+        cache.store(
+            CacheKind::Problems,
+            "5241304",
+            &serde_json::to_string(&problems).unwrap(),
+        );
+        let satori =
+            SimpleSatori::new(client, parser, MemoryTokenStorage::with_token(TOKEN), cache);
+
+        let output = std::env::temp_dir().join(format!("satori-cli-pdf-{}", std::process::id()));
+        let path = satori
+            .pdf("Algo", "A", output.to_str().unwrap(), true)
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"%PDF-1.4 statement");
+        assert_eq!(*requests.borrow(), vec![pdf_url]);
+        let _ = std::fs::remove_dir_all(&output);
+    }
+
+    #[test]
+    fn watch_gives_up_after_timeout() {
+        let queued = fixture!("details").replacen(">ANS<", ">QUE<", 1);
+        let client = FakeClient::new().route("/contest/5241304/results/8812345", &queued);
+        let satori = logged_in(client);
+        assert!(matches!(
+            satori.watch("Algo", "8812345", Duration::ZERO),
+            Err(SatoriError::WatchTimedOut(0))
+        ));
+    }
+
+    #[test]
+    fn best_status_is_newest_of_equally_good() {
+        let results = fixture!("results")
+            .replace(">QUE<", ">MEM<")
+            .replace("<td>B</td>", "<td>A</td>")
+            .replace(">OK<", ">TLE<");
+        let client = FakeClient::new().route(
+            "/contest/5241304/results?results_limit=100&results_page=1&results_filter_problem=5241320",
+            &results,
+        );
+        let satori = logged_in(client);
+
+        let status = satori.status("Algo", "A", StatusMode::Best, false).unwrap();
+        let result = status.result.unwrap();
+        assert_eq!(result.submission_id, "8812345");
+        assert_eq!(result.status, Verdict::Mem);
+        assert_eq!(status.attempts, 2);
+    }
+
+    #[test]
+    fn status_of_closed_problem_is_not_filtered_by_id() {
+        let client = FakeClient::new().route(
+            "/contest/5241304/results?results_limit=100&results_page=1",
+            fixture!("results"),
+        );
+        let satori = logged_in(client);
+
+        let status = satori
+            .status("Algo", "B", StatusMode::Recent, false)
+            .unwrap();
+        let result = status.result.unwrap();
+        assert_eq!(result.problem_code, "B");
+        assert_eq!(status.attempts, 1);
+    }
+
+    #[test]
+    fn submit_to_closed_problem_fails() {
+        let satori = logged_in(FakeClient::new());
+        assert!(matches!(
+            satori.submit("Algo", "B", "Cargo.toml", None),
+            Err(SatoriError::SubmissionClosed)
+        ));
+    }
+
+    #[test]
+    fn submit_rejects_language_not_offered() {
+        let satori = logged_in(FakeClient::new());
+        assert!(matches!(
+            satori.submit("Algo", "A", "Cargo.toml", Some("Haskell")),
+            Err(SatoriError::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[test]
+    fn submit_rejects_language_without_choice() {
+        let client = FakeClient::new().route(
+            "/contest/5241304/submit?select=5241320",
+            fixture!("results"),
+        );
+        let satori = logged_in(client);
+        assert!(matches!(
+            satori.submit("Algo", "A", "Cargo.toml", Some("C++")),
+            Err(SatoriError::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[test]
+    fn submit_returns_new_submission() {
+        let satori = logged_in(FakeClient::new());
+        assert_eq!(
+            satori
+                .submit("Algo", "A", "Cargo.toml", Some("C++"))
+                .unwrap()
+                .submission_id,
+            "8812345"
+        );
     }
 
-    fn submit(&self, contest: &str, problem: &str, file_path: &str) -> SatoriResult<()> {
-        todo!()
+    #[test]
+    fn submit_does_not_return_earlier_submission() {
+        let client = FakeClient::new().route(
+            "/contest/5241304/results?results_filter_problem=5241320",
+            fixture!("results"),
+        );
+        let satori = logged_in(client);
+        assert!(matches!(
+            satori.submit("Algo", "A", "Cargo.toml", Some("C++")),
+            Err(SatoriError::SubmissionNotFound)
+        ));
     }
 }
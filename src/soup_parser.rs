@@ -3,12 +3,44 @@ use crate::satori::*;
 
 use soup::prelude::*;
 
+const LANGUAGE_FIELD: &str = "lang";
+
 pub struct SoupParser {}
 
 impl SoupParser {
     pub fn new() -> Self {
         Self {}
     }
+
+    fn find_contests(&self, page: &str, status: ContestStatus) -> Option<Vec<Contest>> {
+        let soup = soup::Soup::new(page);
+        let table = soup.tag("table").attr("class", "results").find()?;
+        let mut contests = Vec::new();
+        for row in table.tag("tr").find_all().skip(1) {
+            let mut cells = row.tag("td").find_all();
+            let cell = cells.next()?;
+            let name = cell.text().trim().to_string();
+            let description = cells.next()?.text().trim().to_string();
+
+            // Contests waiting for acceptance are listed without a link
+            let (id, status) = match cell.tag("a").find() {
+                Some(anchor) => {
+                    let id = anchor.get("href")?.split('/').nth(2)?.parse().ok()?;
+                    (id, status)
+                }
+                None => (String::new(), ContestStatus::Pending),
+            };
+
+            contests.push(Contest {
+                id,
+                name,
+                description,
+                status,
+            });
+        }
+
+        Some(contests)
+    }
 }
 
 impl SatoriParser for SoupParser {
@@ -30,32 +62,11 @@ impl SatoriParser for SoupParser {
     }
 
     fn find_joined_contests(&self, page: &str) -> Option<Vec<Contest>> {
-        let soup = soup::Soup::new(page);
-        let table = soup.tag("table").attr("class", "results").find()?;
-        let mut contests = Vec::new();
-        for row in table.tag("tr").find_all().skip(1) {
-            let mut cells = row.tag("td").find_all();
-            let cell = cells.next()?;
-            let id = cell
-                .tag("a")
-                .find()?
-                .get("href")
-                .unwrap()
-                .split('/')
-                .skip(2)
-                .next()?
-                .parse()
-                .ok()?;
-            let name = cell.text().trim().to_string();
-            let description = cells.next()?.text().trim().to_string();
-            contests.push(Contest {
-                id,
-                name,
-                description,
-            });
-        }
+        self.find_contests(page, ContestStatus::Active)
+    }
 
-        Some(contests)
+    fn find_archived_contests(&self, page: &str) -> Option<Vec<Contest>> {
+        self.find_contests(page, ContestStatus::Archived)
     }
 
     fn find_problems(&self, page: &str) -> Option<Vec<Problem>> {
@@ -80,8 +91,8 @@ impl SatoriParser for SoupParser {
             let mut contest_id = String::new();
             if let Some(submit_anchor) = cells.next()?.tag("a").find() {
                 submit_url = submit_anchor.get("href").unwrap().to_string();
-                contest_id = submit_url.split('/').skip(2).next()?.parse().ok()?;
-                id = submit_url.split('=').skip(1).next()?.parse().ok()?;
+                contest_id = submit_url.split('/').nth(2)?.parse().ok()?;
+                id = submit_url.split('=').nth(1)?.parse().ok()?;
             }
 
             problems.push(Problem {
@@ -107,7 +118,7 @@ impl SatoriParser for SoupParser {
         let _user = cells.next()?.text().trim().to_string();
         let problem_code = cells.next()?.text().trim().to_string();
         let time = cells.next()?.text().trim().to_string();
-        let status = cells.next()?.text().trim().to_string();
+        let status = Verdict::from(cells.next()?.text().trim());
 
         let results_table = soup.tag("tbody").attr("valign", "top").find()?;
         let mut test_results = Vec::new();
@@ -115,7 +126,7 @@ impl SatoriParser for SoupParser {
         for row in results_table.tag("tr").find_all() {
             let mut cells = row.tag("td").find_all();
             let test_case = cells.next()?.text().trim().to_string();
-            let status = cells.next()?.text().trim().to_string();
+            let status = Verdict::from(cells.next()?.text().trim());
             let time = cells.next()?.text().trim().to_string();
 
             test_results.push(TestCaseResult {
@@ -145,7 +156,7 @@ impl SatoriParser for SoupParser {
             let submission_id = cells.next()?.text().trim().to_string();
             let problem_code = cells.next()?.text().trim().to_string();
             let time = cells.next()?.text().trim().to_string();
-            let status = cells.next()?.text().trim().to_string();
+            let status = Verdict::from(cells.next()?.text().trim());
 
             results.push(ShortResult {
                 submission_id,
@@ -157,4 +168,221 @@ impl SatoriParser for SoupParser {
 
         Some(results)
     }
+
+    fn find_language_choice(&self, page: &str) -> Option<LanguageChoice> {
+        let soup = soup::Soup::new(page);
+        // Other selects on the page, e.g. navigation, are not part of the submit form
+        let form = soup.tag("form").find_all().find(|form| {
+            form.get("action")
+                .is_some_and(|action| action.ends_with("/submit"))
+        })?;
+        let select = form.tag("select").attr("name", LANGUAGE_FIELD).find()?;
+        let field = LANGUAGE_FIELD.to_string();
+
+        let options = select
+            .tag("option")
+            .find_all()
+            .filter_map(|option| {
+                Some(LanguageOption {
+                    value: option.get("value")?,
+                    name: option.text().trim().to_string(),
+                })
+            })
+            .filter(|option| !option.value.is_empty())
+            .collect();
+
+        Some(LanguageChoice { field, options })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fakes::fixture;
+
+    fn contest(id: &str, name: &str, description: &str, status: ContestStatus) -> Contest {
+        Contest {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            status,
+        }
+    }
+
+    fn test_case(test_case: &str, status: Verdict, time: &str) -> TestCaseResult {
+        TestCaseResult {
+            test_case: test_case.to_string(),
+            status,
+            time: time.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_username() {
+        let parser = SoupParser::new();
+        assert_eq!(
+            parser.find_username(fixture!("contest_select")),
+            Some("John Smith".to_string())
+        );
+    }
+
+    #[test]
+    fn finds_no_username_when_logged_out() {
+        let parser = SoupParser::new();
+        assert_eq!(parser.find_username(fixture!("logged_out")), None);
+    }
+
+    #[test]
+    fn finds_joined_contests() {
+        let parser = SoupParser::new();
+        assert_eq!(
+            parser.find_joined_contests(fixture!("contest_select")),
+            Some(vec![
+                contest(
+                    "5241304",
+                    "Algorithms 2023",
+                    "Algorithms and data structures, winter semester",
+                    ContestStatus::Active
+                ),
+                contest("5335171", "Graph Theory", "", ContestStatus::Active),
+                contest(
+                    "",
+                    "Competitive Programming",
+                    "Waiting for acceptance",
+                    ContestStatus::Pending
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn finds_archived_contests() {
+        let parser = SoupParser::new();
+        assert_eq!(
+            parser.find_archived_contests(fixture!("contest_select_archived")),
+            Some(vec![contest(
+                "4873421",
+                "Algorithms 2022",
+                "Algorithms and data structures, winter semester",
+                ContestStatus::Archived
+            )])
+        );
+    }
+
+    #[test]
+    fn finds_problems() {
+        let parser = SoupParser::new();
+        assert_eq!(
+            parser.find_problems(fixture!("problems")),
+            Some(vec![
+                Problem {
+                    contest_id: "5241304".to_string(),
+                    id: "5241320".to_string(),
+                    code: "A".to_string(),
+                    name: "Sum of Two Numbers".to_string(),
+                    pdf_url: "/view/ProblemMapping/5241320/statement_files/_pdf/a.pdf".to_string(),
+                    deadline: "2023-03-01 23:59:59".to_string(),
+                    submit_url: "/contest/5241304/submit?select=5241320".to_string(),
+                },
+                Problem {
+                    contest_id: String::new(),
+                    id: String::new(),
+                    code: "B".to_string(),
+                    name: "Shortest Paths".to_string(),
+                    pdf_url: "/view/ProblemMapping/5241333/statement_files/_pdf/b.pdf".to_string(),
+                    deadline: "2023-02-01 23:59:59".to_string(),
+                    submit_url: String::new(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn finds_results() {
+        let parser = SoupParser::new();
+        assert_eq!(
+            parser.find_results(fixture!("results")),
+            Some(vec![
+                ShortResult {
+                    submission_id: "8812345".to_string(),
+                    problem_code: "A".to_string(),
+                    time: "2023-02-20 12:34:56".to_string(),
+                    status: Verdict::Que,
+                },
+                ShortResult {
+                    submission_id: "8812001".to_string(),
+                    problem_code: "B".to_string(),
+                    time: "2023-02-19 08:00:01".to_string(),
+                    status: Verdict::Ok,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn finds_details() {
+        let parser = SoupParser::new();
+        assert_eq!(
+            parser.find_details(fixture!("details")),
+            Some(ResultDetails {
+                submission_id: "8812001".to_string(),
+                problem_code: "B".to_string(),
+                time: "2023-02-19 08:00:01".to_string(),
+                status: Verdict::Ans,
+                test_results: vec![
+                    test_case("1", Verdict::Ok, "0.01s"),
+                    test_case("2", Verdict::Ans, "0.03s"),
+                    test_case("3", Verdict::Tle, "1.00s"),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn finds_language_choice() {
+        let parser = SoupParser::new();
+        let option = |value: &str, name: &str| LanguageOption {
+            value: value.to_string(),
+            name: name.to_string(),
+        };
+        assert_eq!(
+            parser.find_language_choice(fixture!("submit")),
+            Some(LanguageChoice {
+                field: "lang".to_string(),
+                options: vec![
+                    option("cpp17", "C++17 (g++ 11.2)"),
+                    option("c11", "C (gcc 11.2)"),
+                    option("py3", "Python 3.10"),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn finds_language_choice_only_in_submit_form() {
+        let parser = SoupParser::new();
+        let page = fixture!("submit").replace(
+            "<h3>Submit</h3>",
+            "<form action=\"/contest/select\"><select name=\"lang\"><option value=\"en\">English</option></select></form>",
+        );
+        let choice = parser.find_language_choice(&page).unwrap();
+        assert_eq!(choice.options.len(), 3);
+        assert_eq!(choice.options[0].value, "cpp17");
+    }
+
+    #[test]
+    fn finds_no_language_choice_without_select() {
+        let parser = SoupParser::new();
+        assert_eq!(parser.find_language_choice(fixture!("results")), None);
+    }
+
+    #[test]
+    fn logged_out_page_has_no_data() {
+        let parser = SoupParser::new();
+        let page = fixture!("logged_out");
+        assert_eq!(parser.find_joined_contests(page), None);
+        assert_eq!(parser.find_problems(page), None);
+        assert_eq!(parser.find_results(page), None);
+        assert_eq!(parser.find_details(page), None);
+    }
 }
@@ -0,0 +1,270 @@
+use crate::language::Language;
+use crate::satori::*;
+
+use serde::Serialize;
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// Result of a sample test with the first line where the output differs, if any
+#[derive(Debug, Serialize)]
+pub struct LocalTestResult {
+    #[serde(flatten)]
+    pub result: TestCaseResult,
+    pub difference: Option<String>,
+}
+
+/// Overall status is `CME` if compilation failed, otherwise the first one that is not accepted
+#[derive(Debug, Serialize)]
+pub struct LocalTestReport {
+    pub problem_code: String,
+    pub status: Verdict,
+    pub test_results: Vec<LocalTestResult>,
+}
+
+/// Compiles a solution and runs it against `<name>.in` / `<name>.out` pairs
+pub struct TestRunner {
+    time_limit: Duration,
+}
+
+impl TestRunner {
+    pub fn new(time_limit: Duration) -> TestRunner {
+        TestRunner { time_limit }
+    }
+
+    pub fn run(
+        &self,
+        problem: &str,
+        file_path: &str,
+        tests_dir: &Path,
+    ) -> SatoriResult<LocalTestReport> {
+        let source = Path::new(file_path);
+        if !source.is_file() {
+            return Err(SatoriError::FileError(format!(
+                "{} is not a file",
+                file_path
+            )));
+        }
+        let language = Language::from_path(source)
+            .ok_or_else(|| SatoriError::UnsupportedLanguage(file_path.to_string()))?;
+        let tests = Self::find_tests(tests_dir)?;
+
+        let build_dir = std::env::temp_dir().join(format!("satori-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&build_dir).map_err(file_error)?;
+        let report = self.compile_and_run(problem, language, source, &build_dir, &tests);
+        let _ = std::fs::remove_dir_all(&build_dir);
+        report
+    }
+
+    fn compile_and_run(
+        &self,
+        problem: &str,
+        language: Language,
+        source: &Path,
+        build_dir: &Path,
+        tests: &[(String, PathBuf, PathBuf)],
+    ) -> SatoriResult<LocalTestReport> {
+        if let Some(mut compile) = language.compile_command(source, build_dir) {
+            // Compiler messages go straight to the terminal
+            let status = compile.stdout(Stdio::null()).status().map_err(|error| {
+                SatoriError::FileError(format!("cannot run compiler: {}", error))
+            })?;
+            if !status.success() {
+                return Ok(LocalTestReport {
+                    problem_code: problem.to_string(),
+                    status: Verdict::Cme,
+                    test_results: vec![],
+                });
+            }
+        }
+
+        let mut test_results = vec![];
+        for (name, input, expected) in tests {
+            test_results.push(self.run_test(language, source, build_dir, name, input, expected)?);
+        }
+        let status = test_results
+            .iter()
+            .map(|test| &test.result.status)
+            .find(|status| !status.is_accepted())
+            .cloned()
+            .unwrap_or(Verdict::Ok);
+
+        Ok(LocalTestReport {
+            problem_code: problem.to_string(),
+            status,
+            test_results,
+        })
+    }
+
+    fn run_test(
+        &self,
+        language: Language,
+        source: &Path,
+        build_dir: &Path,
+        name: &str,
+        input: &Path,
+        expected: &Path,
+    ) -> SatoriResult<LocalTestResult> {
+        let output_path = build_dir.join("output");
+        let mut child = language
+            .run_command(source, build_dir)
+            .stdin(File::open(input).map_err(file_error)?)
+            .stdout(File::create(&output_path).map_err(file_error)?)
+            .spawn()
+            .map_err(|error| SatoriError::FileError(format!("cannot run solution: {}", error)))?;
+
+        let start = Instant::now();
+        let exit_status = loop {
+            if let Some(status) = child.try_wait().map_err(file_error)? {
+                break Some(status);
+            }
+            if start.elapsed() > self.time_limit {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        let time = format!("{:.2}s", start.elapsed().as_secs_f64());
+
+        let (status, difference) = match exit_status {
+            None => (Verdict::Tle, None),
+            Some(status) if !status.success() => (Verdict::Rte, None),
+            Some(_) => {
+                let expected = std::fs::read_to_string(expected).map_err(file_error)?;
+                let actual = std::fs::read_to_string(&output_path).map_err(file_error)?;
+                match Self::first_difference(&expected, &actual) {
+                    None => (Verdict::Ok, None),
+                    Some(difference) => (Verdict::Ans, Some(difference)),
+                }
+            }
+        };
+
+        Ok(LocalTestResult {
+            result: TestCaseResult {
+                test_case: name.to_string(),
+                status,
+                time,
+            },
+            difference,
+        })
+    }
+
+    /// Compares outputs token by token on each line, ignoring trailing empty lines
+    fn first_difference(expected: &str, actual: &str) -> Option<String> {
+        let lines = |text: &str| {
+            let mut lines = text
+                .lines()
+                .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+                .collect::<Vec<String>>();
+            while lines.last().is_some_and(|line| line.is_empty()) {
+                lines.pop();
+            }
+            lines
+        };
+        let expected = lines(expected);
+        let actual = lines(actual);
+
+        let length = expected.len().max(actual.len());
+        (0..length).find_map(|i| {
+            let expected_line = expected
+                .get(i)
+                .map_or("<end of output>", |line| line.as_str());
+            let actual_line = actual
+                .get(i)
+                .map_or("<end of output>", |line| line.as_str());
+            match expected_line == actual_line {
+                true => None,
+                false => Some(format!(
+                    "line {}: expected `{}`, got `{}`",
+                    i + 1,
+                    expected_line,
+                    actual_line
+                )),
+            }
+        })
+    }
+
+    /// Tests with an input but no expected output are skipped
+    fn find_tests(tests_dir: &Path) -> SatoriResult<Vec<(String, PathBuf, PathBuf)>> {
+        let entries = std::fs::read_dir(tests_dir).map_err(|error| {
+            SatoriError::FileError(format!("{}: {}", tests_dir.display(), error))
+        })?;
+
+        let mut tests = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "in"))
+            .filter_map(|input| {
+                let expected = input.with_extension("out");
+                let name = input.file_stem()?.to_string_lossy().to_string();
+                expected.is_file().then_some((name, input, expected))
+            })
+            .collect::<Vec<(String, PathBuf, PathBuf)>>();
+        // Numbered tests in numeric order, so 2 comes before 10
+        tests.sort_by_key(|(name, _, _)| (name.parse::<u64>().unwrap_or(u64::MAX), name.clone()));
+
+        if tests.is_empty() {
+            return Err(SatoriError::FileError(format!(
+                "no tests found in {}",
+                tests_dir.display()
+            )));
+        }
+        Ok(tests)
+    }
+}
+
+fn file_error(error: std::io::Error) -> SatoriError {
+    SatoriError::FileError(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_whitespace_and_blank_lines_are_ignored() {
+        assert_eq!(
+            TestRunner::first_difference("1 2\n3\n", "1  2 \n3\n\n\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn token_mismatch_is_reported_with_line() {
+        assert_eq!(
+            TestRunner::first_difference("1 2\n3\n", "1 2\n4\n"),
+            Some("line 2: expected `3`, got `4`".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_line_is_reported() {
+        assert_eq!(
+            TestRunner::first_difference("1\n2\n", "1\n"),
+            Some("line 2: expected `2`, got `<end of output>`".to_string())
+        );
+    }
+
+    #[test]
+    fn tests_are_found_in_numeric_order() {
+        let dir =
+            std::env::temp_dir().join(format!("satori-cli-find-tests-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["10", "2", "1", "sample"] {
+            std::fs::write(dir.join(format!("{}.in", name)), "").unwrap();
+            std::fs::write(dir.join(format!("{}.out", name)), "").unwrap();
+        }
+        std::fs::write(dir.join("3.in"), "").unwrap();
+
+        let tests = TestRunner::find_tests(&dir).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let names = tests
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["1", "2", "10", "sample"]);
+    }
+}
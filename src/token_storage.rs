@@ -1,5 +1,19 @@
 pub trait TokenStorage {
     fn load_token(&self) -> Option<String>;
-    fn save_token(&self, token: &str);
-    fn clear_token(&self);
+    fn save_token(&self, token: &str) -> std::io::Result<()>;
+    fn clear_token(&self) -> std::io::Result<()>;
+}
+
+impl<T: TokenStorage + ?Sized> TokenStorage for Box<T> {
+    fn load_token(&self) -> Option<String> {
+        (**self).load_token()
+    }
+
+    fn save_token(&self, token: &str) -> std::io::Result<()> {
+        (**self).save_token(token)
+    }
+
+    fn clear_token(&self) -> std::io::Result<()> {
+        (**self).clear_token()
+    }
 }
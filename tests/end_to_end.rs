@@ -0,0 +1,215 @@
+//! Runs the binary against a local stand-in for Satori.
+
+mod fake_satori;
+
+use fake_satori::{FakeSatori, LOGIN, PASSWORD};
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Fresh home directory, so tokens, caches and configuration of tests don't mix
+struct Cli {
+    home: PathBuf,
+    url: String,
+}
+
+impl Cli {
+    fn new(server: &FakeSatori, name: &str) -> Cli {
+        let home =
+            std::env::temp_dir().join(format!("satori-cli-e2e-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&home);
+        std::fs::create_dir_all(&home).unwrap();
+        Cli {
+            home,
+            url: server.url.clone(),
+        }
+    }
+
+    fn run(&self, args: &[&str], credentials: Option<(&str, &str)>) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_satori-cli"));
+        command
+            .current_dir(&self.home)
+            .env("HOME", &self.home)
+            .env_remove("SATORI_LOGIN")
+            .env_remove("SATORI_PASSWORD")
+            .env_remove("SATORI_URL")
+            .env_remove("SATORI_TOKEN_NAME")
+            .args(["--url", &self.url, "--non-interactive", "--retries", "0"])
+            .args(args);
+        if let Some((login, password)) = credentials {
+            command
+                .env("SATORI_LOGIN", login)
+                .env("SATORI_PASSWORD", password);
+        }
+        command.output().unwrap()
+    }
+}
+
+impl Drop for Cli {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn login_stores_token_for_later_commands() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "login");
+
+    let output = cli.run(&["login"], Some((LOGIN, PASSWORD)));
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("Logged in as John Smith."));
+
+    let output = cli.run(&["--format", "json", "contests"], None);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("\"name\":\"Algorithms 2023\""));
+}
+
+#[test]
+fn token_is_stored_per_instance() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "instance");
+
+    let output = cli.run(&["login"], Some((LOGIN, PASSWORD)));
+    assert_eq!(output.status.code(), Some(0));
+
+    let data = cli.home.join(".local/share/satori-cli");
+    let port = server.url.rsplit(':').next().unwrap();
+    assert!(data.join(format!("token@127.0.0.1_{}.txt", port)).is_file());
+    assert!(!data.join("token.txt").exists());
+}
+
+#[test]
+fn profile_names_outside_data_directory_are_rejected() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "profile-name");
+
+    let output = cli.run(&["profile", "add", ".."], None);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(!cli.home.join(".config/satori-cli/profiles.toml").exists());
+
+    let output = cli.run(&["--profile", "../x", "contests"], None);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn invalid_time_limits_are_rejected() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "time-limit");
+
+    for limit in ["-1", "0", "nan", "inf"] {
+        let output = cli.run(&["test", "--time-limit", limit, "Cargo.toml"], None);
+        assert_eq!(output.status.code(), Some(2), "--time-limit {}", limit);
+    }
+}
+
+#[test]
+fn password_of_another_account_is_not_used() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "other-account");
+
+    let output = cli.run(&["login", "-u", LOGIN], Some(("someone-else", PASSWORD)));
+    assert_eq!(output.status.code(), Some(10));
+
+    let output = cli.run(&["login", "-u", LOGIN], Some((LOGIN, PASSWORD)));
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn wrong_password_fails() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "wrong-password");
+
+    let output = cli.run(&["login"], Some((LOGIN, "wrong")));
+    assert_eq!(output.status.code(), Some(11));
+    assert!(stdout(&output).contains("Login failed."));
+}
+
+#[test]
+fn commands_log_in_on_demand() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "on-demand");
+
+    let output = cli.run(&["problems", "-c", "Algo"], Some((LOGIN, PASSWORD)));
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("Sum of Two Numbers"));
+
+    let output = cli.run(&["problems", "-c", "Algo"], None);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn not_logged_in_without_credentials() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "no-credentials");
+
+    let output = cli.run(&["contests"], None);
+    assert_eq!(output.status.code(), Some(10));
+}
+
+#[test]
+fn submit_uploads_file_with_language() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "submit");
+    std::fs::write(cli.home.join("main.cpp"), "int main() {}\n").unwrap();
+
+    let output = cli.run(
+        &["submit", "-c", "Algo", "-p", "A", "-f", "main.cpp"],
+        Some((LOGIN, PASSWORD)),
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("8900000"));
+
+    let submissions = server.submissions();
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].problem, "5241320");
+    assert_eq!(submissions[0].lang.as_deref(), Some("cpp17"));
+    assert_eq!(submissions[0].file_name, "main.cpp");
+    assert_eq!(submissions[0].code, "int main() {}\n");
+}
+
+#[test]
+fn submit_wait_prints_one_json_object() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "submit-wait-json");
+    std::fs::write(cli.home.join("main.cpp"), "int main() {}\n").unwrap();
+
+    let output = cli.run(
+        &[
+            "--format", "json", "submit", "-c", "Algo", "-p", "A", "-f", "main.cpp", "--wait",
+        ],
+        Some((LOGIN, PASSWORD)),
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = stdout(&output);
+    assert_eq!(stdout.lines().count(), 1);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["submission"]["submission_id"], "8900000");
+    assert_eq!(json["result"]["status"], "ANS");
+}
+
+#[test]
+fn failed_logins_stay_out_of_json_output() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "failed-login-json");
+
+    let output = cli.run(&["--format", "json", "contests"], Some((LOGIN, "wrong")));
+    assert_eq!(output.status.code(), Some(10));
+    assert_eq!(stdout(&output).trim(), r#"{"error":"not_logged_in"}"#);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("login_failed"));
+}
+
+#[test]
+fn pdf_is_downloaded() {
+    let server = FakeSatori::start();
+    let cli = Cli::new(&server, "pdf");
+
+    let output = cli.run(&["pdf", "-c", "Algo", "-p", "A"], Some((LOGIN, PASSWORD)));
+    assert_eq!(output.status.code(), Some(0));
+    let pdf = std::fs::read(cli.home.join("Algorithms 2023").join("A.pdf")).unwrap();
+    assert!(pdf.starts_with(b"%PDF"));
+}
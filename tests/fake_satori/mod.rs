@@ -0,0 +1,207 @@
+//! Stand-in for Satori serving the pages from `tests/fixtures`, so the real HTTP client
+//! (cookies, redirects, multipart uploads) can be tested end to end.
+
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub const LOGIN: &str = "jsmith";
+pub const PASSWORD: &str = "secret";
+const TOKEN_NAME: &str = "satori_token";
+const TOKEN: &str = "e2e-token";
+const FIRST_SUBMISSION_ID: u32 = 8900000;
+const PDF: &[u8] = b"%PDF-1.4 fake statement";
+
+macro_rules! fixture {
+    ($name:literal) => {
+        include_str!(concat!("../fixtures/", $name, ".html"))
+    };
+}
+
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub id: String,
+    pub problem: String,
+    pub lang: Option<String>,
+    pub file_name: String,
+    pub code: String,
+}
+
+pub struct FakeSatori {
+    pub url: String,
+    server: Arc<Server>,
+    submissions: Arc<Mutex<Vec<Submission>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FakeSatori {
+    pub fn start() -> FakeSatori {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let port = server.server_addr().to_ip().unwrap().port();
+        let submissions = Arc::new(Mutex::new(vec![]));
+
+        let thread = {
+            let server = Arc::clone(&server);
+            let submissions = Arc::clone(&submissions);
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, &submissions);
+                }
+            })
+        };
+
+        FakeSatori {
+            url: format!("http://127.0.0.1:{}", port),
+            server,
+            submissions,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn submissions(&self) -> Vec<Submission> {
+        self.submissions.lock().unwrap().clone()
+    }
+}
+
+impl Drop for FakeSatori {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle(mut request: Request, submissions: &Mutex<Vec<Submission>>) {
+    let mut body = Vec::new();
+    let _ = request.as_reader().read_to_end(&mut body);
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default().to_string();
+
+    let logged_in = header(&request, "Cookie")
+        .is_some_and(|cookie| cookie.contains(&format!("{}={}", TOKEN_NAME, TOKEN)));
+
+    let response = match (request.method(), path.as_str()) {
+        (Method::Post, "/login") => {
+            let form = String::from_utf8_lossy(&body).to_string();
+            let valid = form.contains(&format!("login={}", LOGIN))
+                && form.contains(&format!("password={}", PASSWORD));
+            match valid {
+                true => redirect("/").with_header(
+                    Header::from_bytes("Set-Cookie", format!("{}={}; Path=/", TOKEN_NAME, TOKEN))
+                        .unwrap(),
+                ),
+                false => html(fixture!("logged_out")),
+            }
+        }
+        _ if !logged_in => html(fixture!("logged_out")),
+        (Method::Post, "/contest/5241304/submit") => {
+            let content_type = header(&request, "Content-Type").unwrap_or_default();
+            let submission = parse_submission(&content_type, &body, submissions);
+            match submission {
+                Some(submission) => {
+                    submissions.lock().unwrap().push(submission);
+                    redirect("/contest/5241304/results")
+                }
+                None => Response::from_string("bad submission").with_status_code(400),
+            }
+        }
+        (Method::Get, "/" | "/contest/select") if url.ends_with("archived=1") => {
+            html(fixture!("contest_select_archived"))
+        }
+        (Method::Get, "/" | "/contest/select") => html(fixture!("contest_select")),
+        (Method::Get, "/contest/5241304/problems") => html(fixture!("problems")),
+        (Method::Get, "/contest/5241304/submit") => html(fixture!("submit")),
+        (Method::Get, "/contest/5241304/results") => html(&results_page(submissions)),
+        (Method::Get, "/contest/5241304/results/8812001") => html(fixture!("details")),
+        // Every new submission is judged at once, with the fixture verdict
+        (Method::Get, path) if path.starts_with("/contest/5241304/results/89") => {
+            html(fixture!("details"))
+        }
+        (Method::Get, "/view/ProblemMapping/5241320/statement_files/_pdf/a.pdf") => {
+            Response::from_data(PDF.to_vec())
+        }
+        _ => Response::from_string("not found").with_status_code(404),
+    };
+    let _ = request.respond(response);
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.to_string())
+}
+
+fn html(page: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(page)
+        .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap())
+}
+
+fn redirect(location: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string("")
+        .with_status_code(302)
+        .with_header(Header::from_bytes("Location", location).unwrap())
+}
+
+/// Fixture results with accepted submissions on top, newest first
+fn results_page(submissions: &Mutex<Vec<Submission>>) -> String {
+    let header = "<tr><th>Id</th><th>Problem</th><th>Time</th><th>Status</th></tr>";
+    let rows = submissions
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .map(|submission| {
+            format!(
+                "<tr><td>{}</td><td>A</td><td>2023-02-21 10:00:00</td><td>QUE</td></tr>",
+                submission.id
+            )
+        })
+        .collect::<String>();
+    fixture!("results").replace(header, &format!("{}{}", header, rows))
+}
+
+/// Reads `problem`, `lang` and `codefile` fields of a multipart form
+fn parse_submission(
+    content_type: &str,
+    body: &[u8],
+    submissions: &Mutex<Vec<Submission>>,
+) -> Option<Submission> {
+    let boundary = content_type.split("boundary=").nth(1)?;
+    let body = String::from_utf8_lossy(body);
+
+    let mut problem = None;
+    let mut lang = None;
+    let mut file = None;
+    for part in body.split(&format!("--{}", boundary)) {
+        // Skips the empty preamble and the closing `--`
+        let Some((headers, content)) = part.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let content = content.strip_suffix("\r\n").unwrap_or(content).to_string();
+        let name = headers.split("name=\"").nth(1)?.split('"').next()?;
+        let file_name = headers
+            .split("filename=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next());
+        match (name, file_name) {
+            ("problem", _) => problem = Some(content),
+            ("lang", _) => lang = Some(content),
+            ("codefile", Some(file_name)) => file = Some((file_name.to_string(), content)),
+            _ => {}
+        }
+    }
+
+    let (file_name, code) = file?;
+    let id = FIRST_SUBMISSION_ID + submissions.lock().unwrap().len() as u32;
+    Some(Submission {
+        id: id.to_string(),
+        problem: problem?,
+        lang,
+        file_name,
+        code,
+    })
+}